@@ -0,0 +1,54 @@
+use floating_particles::ParticleSystem;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const CONNECTION_DISTANCE: f64 = 100.0;
+const COUNTS: [usize; 6] = [250, 500, 1_000, 2_500, 5_000, 10_000];
+
+fn time_per_frame(system: &mut ParticleSystem) -> (Duration, usize) {
+    let mut lines = 0;
+    let start = Instant::now();
+    let mut frames = 0u32;
+
+    while frames < 10 || start.elapsed() < Duration::from_millis(500) {
+        system.update();
+        lines = black_box(system.connection_data()).len() / 5;
+        frames += 1;
+    }
+
+    (start.elapsed() / frames, lines)
+}
+
+fn run(label: &str, size_for: impl Fn(usize) -> (f64, f64)) {
+    println!("{label}");
+    println!(
+        "{:>8} {:>12} {:>14} {:>10}",
+        "n", "size", "per frame", "lines"
+    );
+
+    for &n in &COUNTS {
+        let (width, height) = size_for(n);
+        let mut system = ParticleSystem::with_seed(width, height, n, CONNECTION_DISTANCE, 42);
+        let (elapsed, lines) = time_per_frame(&mut system);
+        println!(
+            "{:>8} {:>12} {:>14.3?} {:>10}",
+            n,
+            format!("{width:.0}x{height:.0}"),
+            elapsed,
+            lines
+        );
+    }
+    println!();
+}
+
+fn main() {
+    // 固定屏幕尺寸：密度随粒子数增加
+    run("fixed 1920x1080 viewport", |_| (1920.0, 1080.0));
+
+    // 固定密度（约每 8000 平方像素一个粒子）：耗时应随粒子数线性增长
+    run("constant density", |n| {
+        let area = n as f64 * 8000.0;
+        let width = (area * 16.0 / 9.0).sqrt();
+        (width, width * 9.0 / 16.0)
+    });
+}
@@ -0,0 +1,211 @@
+use crate::{params, ParticleError, ParticleSystem};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttractorKind {
+    /// Pulls particles towards the centre.
+    Attract = 0,
+    /// Pushes particles away from the centre.
+    Repel = 1,
+    /// Swirls particles around the centre, counter-clockwise for positive
+    /// strength.
+    Vortex = 2,
+}
+
+/// How a point force weakens between its centre and its radius.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Falloff {
+    Constant = 0,
+    Linear = 1,
+    /// `(1 - d / radius)²`, the same curve the mouse uses.
+    Quadratic = 2,
+    Smooth = 3,
+}
+
+impl Falloff {
+    fn factor(self, distance: f64, radius: f64) -> f64 {
+        let t = 1.0 - distance / radius;
+        match self {
+            Falloff::Constant => 1.0,
+            Falloff::Linear => t,
+            Falloff::Quadratic => t * t,
+            Falloff::Smooth => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Attractor {
+    pub id: u32,
+    pub x: f64,
+    pub y: f64,
+    pub radius: f64,
+    pub strength: f64,
+    pub kind: AttractorKind,
+    pub falloff: Falloff,
+    pub remaining_ms: Option<f64>,
+}
+
+impl Attractor {
+    /// Checks the fields against the rules the setters enforce.
+    pub fn validate(&self) -> Result<(), ParticleError> {
+        params::finite("x", self.x)?;
+        params::finite("y", self.y)?;
+        params::non_negative("radius", self.radius)?;
+        params::finite("strength", self.strength)?;
+        if let Some(remaining) = self.remaining_ms {
+            params::non_negative("lifetime", remaining)?;
+        }
+        Ok(())
+    }
+
+    /// Velocity change this attractor applies to a particle at `(x, y)`.
+    pub fn force_at(&self, x: f64, y: f64) -> (f64, f64) {
+        let dx = self.x - x;
+        let dy = self.y - y;
+        let distance_sq = dx * dx + dy * dy;
+        if distance_sq >= self.radius * self.radius || distance_sq == 0.0 {
+            return (0.0, 0.0);
+        }
+
+        let distance = distance_sq.sqrt();
+        let magnitude = self.strength * self.falloff.factor(distance, self.radius) / distance;
+        match self.kind {
+            AttractorKind::Attract => (dx * magnitude, dy * magnitude),
+            AttractorKind::Repel => (-dx * magnitude, -dy * magnitude),
+            AttractorKind::Vortex => (dy * magnitude, -dx * magnitude),
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ParticleSystem {
+    /// Adds a point force and returns its id. `strength` is the velocity
+    /// change per step at the centre, before falloff.
+    pub fn add_attractor(
+        &mut self,
+        x: f64,
+        y: f64,
+        radius: f64,
+        strength: f64,
+        kind: AttractorKind,
+        falloff: Falloff,
+    ) -> Result<u32, ParticleError> {
+        let x = params::finite("x", x)?;
+        let y = params::finite("y", y)?;
+        let radius = params::non_negative("radius", radius)?;
+        let strength = params::finite("strength", strength)?;
+        let id = self.next_attractor_id;
+        self.next_attractor_id = self.next_attractor_id.wrapping_add(1).max(1);
+        self.attractors.push(Attractor {
+            id,
+            x,
+            y,
+            radius,
+            strength,
+            kind,
+            falloff,
+            remaining_ms: None,
+        });
+        Ok(id)
+    }
+
+    /// Returns `Ok(false)` when no attractor has `id`.
+    pub fn move_attractor(&mut self, id: u32, x: f64, y: f64) -> Result<bool, ParticleError> {
+        let x = params::finite("x", x)?;
+        let y = params::finite("y", y)?;
+        Ok(self.with_attractor(id, |attractor| {
+            attractor.x = x;
+            attractor.y = y;
+        }))
+    }
+
+    pub fn set_attractor_radius(&mut self, id: u32, radius: f64) -> Result<bool, ParticleError> {
+        let radius = params::non_negative("radius", radius)?;
+        Ok(self.with_attractor(id, |attractor| attractor.radius = radius))
+    }
+
+    pub fn set_attractor_strength(
+        &mut self,
+        id: u32,
+        strength: f64,
+    ) -> Result<bool, ParticleError> {
+        let strength = params::finite("strength", strength)?;
+        Ok(self.with_attractor(id, |attractor| attractor.strength = strength))
+    }
+
+    pub fn set_attractor_kind(&mut self, id: u32, kind: AttractorKind) -> bool {
+        self.with_attractor(id, |attractor| attractor.kind = kind)
+    }
+
+    pub fn set_attractor_falloff(&mut self, id: u32, falloff: Falloff) -> bool {
+        self.with_attractor(id, |attractor| attractor.falloff = falloff)
+    }
+
+    /// Removes the attractor automatically after `lifetime_ms` of simulated
+    /// time.
+    pub fn set_attractor_lifetime(
+        &mut self,
+        id: u32,
+        lifetime_ms: f64,
+    ) -> Result<bool, ParticleError> {
+        let lifetime_ms = params::non_negative("lifetime", lifetime_ms)?;
+        Ok(self.with_attractor(id, |attractor| attractor.remaining_ms = Some(lifetime_ms)))
+    }
+
+    /// Makes the attractor permanent again.
+    pub fn clear_attractor_lifetime(&mut self, id: u32) -> bool {
+        self.with_attractor(id, |attractor| attractor.remaining_ms = None)
+    }
+
+    pub fn remove_attractor(&mut self, id: u32) -> bool {
+        let len = self.attractors.len();
+        self.attractors.retain(|attractor| attractor.id != id);
+        self.attractors.len() != len
+    }
+
+    pub fn clear_attractors(&mut self) {
+        self.attractors.clear();
+    }
+
+    pub fn attractor_count(&self) -> usize {
+        self.attractors.len()
+    }
+
+    pub fn attractor_ids(&self) -> Vec<u32> {
+        self.attractors
+            .iter()
+            .map(|attractor| attractor.id)
+            .collect()
+    }
+}
+
+impl ParticleSystem {
+    fn with_attractor(&mut self, id: u32, f: impl FnOnce(&mut Attractor)) -> bool {
+        match self
+            .attractors
+            .iter_mut()
+            .find(|attractor| attractor.id == id)
+        {
+            Some(attractor) => {
+                f(attractor);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn age_attractors(&mut self, dt_ms: f64) {
+        self.attractors
+            .retain_mut(|attractor| match &mut attractor.remaining_ms {
+                Some(remaining) => {
+                    *remaining -= dt_ms;
+                    *remaining > 0.0
+                }
+                None => true,
+            });
+    }
+}
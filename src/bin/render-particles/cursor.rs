@@ -0,0 +1,60 @@
+/// Scripted cursor movement: keyframes of `seconds x y`, or `seconds off`
+/// for a cursor outside the page. Positions are interpolated linearly
+/// between consecutive keyframes and held after the last one.
+pub struct CursorPath {
+    keys: Vec<(f64, Option<(f64, f64)>)>,
+}
+
+// 光标离开页面时使用的位置，与系统初始的鼠标位置一致
+const OUTSIDE: (f64, f64) = (-1000.0, -1000.0);
+
+impl CursorPath {
+    pub fn parse(text: &str) -> Result<CursorPath, String> {
+        let mut keys: Vec<(f64, Option<(f64, f64)>)> = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: &str| format!("line {}: {message}", index + 1);
+            let number = |field: &str| {
+                field
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|value| value.is_finite())
+                    .ok_or_else(|| error(&format!("`{field}` is not a number")))
+            };
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let key = match fields.as_slice() {
+                [time, "off"] => (number(time)?, None),
+                [time, x, y] => (number(time)?, Some((number(x)?, number(y)?))),
+                _ => return Err(error("expected `seconds x y` or `seconds off`")),
+            };
+            if keys.last().is_some_and(|last| key.0 < last.0) {
+                return Err(error("keyframes must be in time order"));
+            }
+            keys.push(key);
+        }
+        Ok(CursorPath { keys })
+    }
+
+    /// Cursor position at `time` seconds; outside the page before the first
+    /// keyframe.
+    pub fn position(&self, time: f64) -> (f64, f64) {
+        let next = self.keys.partition_point(|key| key.0 <= time);
+        let Some(&(start, from)) = next.checked_sub(1).and_then(|i| self.keys.get(i)) else {
+            return OUTSIDE;
+        };
+        let Some(from) = from else {
+            return OUTSIDE;
+        };
+        match self.keys.get(next) {
+            Some(&(end, Some(to))) if end > start => {
+                let t = (time - start) / (end - start);
+                (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t)
+            }
+            _ => from,
+        }
+    }
+}
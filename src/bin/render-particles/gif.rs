@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+// 量化时每个通道保留的位数，直方图共 2^15 个桶
+const BITS: u32 = 5;
+const BUCKETS: usize = 1 << (BITS * 3);
+const MAX_CODE: u16 = 4095;
+
+/// Looping animated GIF with a 256-colour palette per frame.
+pub struct GifWriter<W: Write> {
+    out: W,
+    width: u16,
+    height: u16,
+}
+
+impl<W: Write> GifWriter<W> {
+    pub fn new(mut out: W, width: u32, height: u32) -> io::Result<GifWriter<W>> {
+        let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "GIF images are limited to 65535 pixels per side",
+            ));
+        };
+
+        out.write_all(b"GIF89a")?;
+        out.write_all(&width.to_le_bytes())?;
+        out.write_all(&height.to_le_bytes())?;
+        // 不使用全局调色板
+        out.write_all(&[0, 0, 0])?;
+        // NETSCAPE2.0 扩展：无限循环
+        out.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+        Ok(GifWriter { out, width, height })
+    }
+
+    /// Writes an RGBA8 frame shown for `delay` hundredths of a second,
+    /// compositing transparent pixels over black.
+    pub fn write_frame(&mut self, pixels: &[u8], delay: u16) -> io::Result<()> {
+        let (palette, indices) = quantize(pixels);
+
+        let out = &mut self.out;
+        out.write_all(&[0x21, 0xf9, 0x04, 0x04])?;
+        out.write_all(&delay.to_le_bytes())?;
+        out.write_all(&[0, 0])?;
+
+        out.write_all(&[0x2c, 0, 0, 0, 0])?;
+        out.write_all(&self.width.to_le_bytes())?;
+        out.write_all(&self.height.to_le_bytes())?;
+        // 局部调色板，256 色
+        out.write_all(&[0x87])?;
+        out.write_all(&palette)?;
+
+        out.write_all(&[8])?;
+        for block in lzw(&indices).chunks(255) {
+            out.write_all(&[block.len() as u8])?;
+            out.write_all(block)?;
+        }
+        out.write_all(&[0])
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.out.write_all(&[0x3b])?;
+        Ok(self.out)
+    }
+}
+
+/// Popularity quantizer: the 256 most common colours at 5 bits per channel
+/// become the palette, which suits frames made of a few flat colours and
+/// their anti-aliased edges. Returns 768 bytes of palette and one index per
+/// pixel.
+fn quantize(pixels: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let colors: Vec<[u8; 3]> = pixels
+        .chunks_exact(4)
+        .map(|pixel| {
+            let alpha = u16::from(pixel[3]);
+            [0, 1, 2].map(|i| ((u16::from(pixel[i]) * alpha + 127) / 255) as u8)
+        })
+        .collect();
+    let bucket = |[r, g, b]: [u8; 3]| {
+        let shift = 8 - BITS;
+        (usize::from(r >> shift) << (BITS * 2))
+            | (usize::from(g >> shift) << BITS)
+            | usize::from(b >> shift)
+    };
+
+    let mut counts = vec![0u32; BUCKETS];
+    let mut sums = vec![[0u64; 3]; BUCKETS];
+    for &color in &colors {
+        let index = bucket(color);
+        counts[index] += 1;
+        for (sum, channel) in sums[index].iter_mut().zip(color) {
+            *sum += u64::from(channel);
+        }
+    }
+
+    let mut used: Vec<usize> = (0..BUCKETS).filter(|&i| counts[i] > 0).collect();
+    used.sort_by_key(|&i| (std::cmp::Reverse(counts[i]), i));
+    let entries: Vec<[u8; 3]> = used
+        .iter()
+        .take(256)
+        .map(|&i| sums[i].map(|sum| (sum / u64::from(counts[i])) as u8))
+        .collect();
+
+    // 每个桶映射到最近的调色板颜色，结果按桶缓存
+    let mut nearest = vec![u8::MAX; BUCKETS];
+    let mut mapped = vec![false; BUCKETS];
+    let indices = colors
+        .iter()
+        .map(|&color| {
+            let index = bucket(color);
+            if !mapped[index] {
+                let average = sums[index].map(|sum| (sum / u64::from(counts[index])) as i32);
+                nearest[index] = (0..entries.len())
+                    .min_by_key(|&e| {
+                        (0..3)
+                            .map(|c| (average[c] - i32::from(entries[e][c])).pow(2))
+                            .sum::<i32>()
+                    })
+                    .unwrap_or(0) as u8;
+                mapped[index] = true;
+            }
+            nearest[index]
+        })
+        .collect();
+
+    let mut palette = vec![0; 768];
+    for (slot, entry) in palette.chunks_exact_mut(3).zip(&entries) {
+        slot.copy_from_slice(entry);
+    }
+    (palette, indices)
+}
+
+/// Variable-width LZW as used by GIF, for 8-bit indices.
+fn lzw(indices: &[u8]) -> Vec<u8> {
+    const CLEAR: u16 = 256;
+    const END: u16 = 257;
+
+    let mut out = Vec::new();
+    let (mut bits, mut count) = (0u32, 0u32);
+    let mut emit = |code: u16, width: u32, out: &mut Vec<u8>| {
+        bits |= u32::from(code) << count;
+        count += width;
+        while count >= 8 {
+            out.push(bits as u8);
+            bits >>= 8;
+            count -= 8;
+        }
+    };
+
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = END + 1;
+    let mut width = 9;
+    emit(CLEAR, width, &mut out);
+
+    let Some((&first, rest)) = indices.split_first() else {
+        emit(END, width, &mut out);
+        out.push(bits as u8);
+        return out;
+    };
+    let mut prefix = u16::from(first);
+    for &index in rest {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+        emit(prefix, width, &mut out);
+        if next < MAX_CODE {
+            table.insert((prefix, index), next);
+            next += 1;
+            if next > 1 << width && width < 12 {
+                width += 1;
+            }
+        } else {
+            // 码表已满，重新开始
+            emit(CLEAR, width, &mut out);
+            table.clear();
+            next = END + 1;
+            width = 9;
+        }
+        prefix = u16::from(index);
+    }
+    emit(prefix, width, &mut out);
+    emit(END, width, &mut out);
+    if count > 0 {
+        out.push(bits as u8);
+    }
+    out
+}
@@ -0,0 +1,337 @@
+//! Renders a particle animation headlessly to a PNG sequence, an animated
+//! GIF or a Y4M stream.
+
+mod cursor;
+mod gif;
+mod png;
+mod y4m;
+
+use cursor::CursorPath;
+use floating_particles::{ParticleConfig, ParticleSystem, Renderer};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: render-particles [OPTIONS] --output <PATH>
+
+Options:
+  --config <FILE>       JSON config file
+  --preset <NAME>       built-in preset (default, constellation, snow, swarm)
+  --seed <SEED>         layout seed, a number or any string
+  --size <WxH>          canvas size in pixels (default: from the config)
+  --scale <FACTOR>      output resolution relative to the canvas (default: 1)
+  --duration <SECONDS>  length of the animation (default: 5)
+  --fps <N>             frames per second (default: 30)
+  --loop                make the motion loop seamlessly over the duration
+  --cursor <FILE>       scripted cursor path, lines of `seconds x y` or `seconds off`
+  --background <COLOR>  #rrggbb or #rrggbbaa (default: #000000)
+  --format <FORMAT>     png, gif or y4m (default: from the output extension)
+  --output <PATH>       directory for PNG frames, a .gif or .y4m file, or `-`
+                        to write Y4M to stdout
+  -h, --help            print this help";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Png,
+    Gif,
+    Y4m,
+}
+
+struct Options {
+    config: ParticleConfig,
+    scale: f64,
+    duration: f64,
+    fps: u32,
+    cursor: Option<CursorPath>,
+    background: [f64; 4],
+    format: Format,
+    output: PathBuf,
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {message}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match render(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut config_path = None;
+    let mut preset = None;
+    let mut seed = None;
+    let mut size = None;
+    let mut scale = 1.0;
+    let mut duration = 5.0;
+    let mut fps = 30;
+    let mut looping = false;
+    let mut cursor_path = None;
+    let mut background = [0.0, 0.0, 0.0, 1.0];
+    let mut format = None;
+    let mut output = None;
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let mut value = || {
+            args.next()
+                .map(String::as_str)
+                .ok_or_else(|| format!("{flag} needs a value"))
+        };
+        match flag.as_str() {
+            "--config" => config_path = Some(value()?.to_string()),
+            "--preset" => preset = Some(value()?.to_string()),
+            "--seed" => seed = Some(value()?.to_string()),
+            "--size" => size = Some(parse_size(value()?)?),
+            "--scale" => scale = parse_number(flag, value()?, 0.01, 16.0)?,
+            "--duration" => duration = parse_number(flag, value()?, 0.0, 3600.0)?,
+            "--fps" => fps = parse_whole(flag, value()?, 1, 240)?,
+            "--loop" => looping = true,
+            "--cursor" => cursor_path = Some(value()?.to_string()),
+            "--background" => background = parse_color(value()?)?,
+            "--format" => format = Some(parse_format(value()?)?),
+            "--output" | "-o" => output = Some(PathBuf::from(value()?)),
+            _ => return Err(format!("unknown option `{flag}`")),
+        }
+    }
+
+    let mut config = match (config_path, preset) {
+        (Some(_), Some(_)) => return Err("--config and --preset cannot be combined".into()),
+        (Some(path), None) => {
+            let text = fs::read_to_string(&path).map_err(|error| format!("{path}: {error}"))?;
+            ParticleConfig::from_json(&text).map_err(|error| format!("{path}: {error}"))?
+        }
+        (None, Some(name)) => ParticleConfig::preset(&name).map_err(|error| error.to_string())?,
+        (None, None) => ParticleConfig::default(),
+    };
+    if let Some((width, height)) = size {
+        config.width = f64::from(width);
+        config.height = f64::from(height);
+    }
+    if looping {
+        config.loop_period_ms = duration * 1000.0;
+    }
+    if let Some(seed) = seed {
+        config.set_string_seed(&seed);
+    }
+
+    let cursor = match cursor_path {
+        Some(path) => {
+            let text = fs::read_to_string(&path).map_err(|error| format!("{path}: {error}"))?;
+            Some(CursorPath::parse(&text).map_err(|error| format!("{path}: {error}"))?)
+        }
+        None => None,
+    };
+
+    let output = output.ok_or("--output is required")?;
+    let format = match format {
+        Some(format) => format,
+        None => match output.extension().and_then(|ext| ext.to_str()) {
+            Some("gif") => Format::Gif,
+            Some("y4m") => Format::Y4m,
+            _ if output.as_os_str() == "-" => Format::Y4m,
+            _ => Format::Png,
+        },
+    };
+    if output.as_os_str() == "-" && format != Format::Y4m {
+        return Err("only Y4M output can be written to stdout".into());
+    }
+
+    Ok(Options {
+        config,
+        scale,
+        duration,
+        fps,
+        cursor,
+        background,
+        format,
+        output,
+    })
+}
+
+fn render(options: &Options) -> Result<(), String> {
+    let mut system =
+        ParticleSystem::from_config(&options.config).map_err(|error| error.to_string())?;
+    // 未指定种子时随机选取，打印出来方便复现满意的结果
+    if options.config.seed().is_none() {
+        eprintln!("seed: {}", system.seed());
+    }
+    let width = (options.config.width * options.scale).round().max(1.0) as u32;
+    let height = (options.config.height * options.scale).round().max(1.0) as u32;
+    let mut renderer = Renderer::new(width, height).map_err(|error| error.to_string())?;
+    let [r, g, b, a] = options.background;
+    renderer
+        .set_background(r, g, b, a)
+        .map_err(|error| error.to_string())?;
+
+    let frames = (options.duration * f64::from(options.fps)).round() as u32;
+    let frame_ms = 1000.0 / f64::from(options.fps);
+    let step_ms = system.fixed_timestep();
+    let mut steps = 0;
+    let mut sink = Sink::open(options, width, height).map_err(|error| error.to_string())?;
+
+    for frame in 0..frames {
+        if let Some(cursor) = &options.cursor {
+            let (x, y) = cursor.position(f64::from(frame) / f64::from(options.fps));
+            system.update_mouse_position(x, y);
+        }
+        renderer.render(&system);
+        sink.write_frame(frame, renderer.pixels())
+            .map_err(|error| format!("{}: {error}", options.output.display()))?;
+        // 步数由帧时刻直接算出，不累计浮点误差，循环视频的末帧与首帧相接
+        let target = (f64::from(frame + 1) * frame_ms / step_ms).round() as u64;
+        while steps < target {
+            system.update();
+            steps += 1;
+        }
+    }
+    sink.finish()
+        .map_err(|error| format!("{}: {error}", options.output.display()))
+}
+
+enum Sink {
+    Png {
+        directory: PathBuf,
+        width: u32,
+        height: u32,
+    },
+    Gif {
+        writer: gif::GifWriter<BufWriter<File>>,
+        fps: u32,
+    },
+    Y4m {
+        writer: y4m::Y4mWriter<BufWriter<Box<dyn Write>>>,
+    },
+}
+
+impl Sink {
+    fn open(options: &Options, width: u32, height: u32) -> io::Result<Sink> {
+        Ok(match options.format {
+            Format::Png => {
+                fs::create_dir_all(&options.output)?;
+                Sink::Png {
+                    directory: options.output.clone(),
+                    width,
+                    height,
+                }
+            }
+            Format::Gif => Sink::Gif {
+                writer: gif::GifWriter::new(
+                    BufWriter::new(File::create(&options.output)?),
+                    width,
+                    height,
+                )?,
+                fps: options.fps,
+            },
+            Format::Y4m => {
+                let out: Box<dyn Write> = if options.output == Path::new("-") {
+                    Box::new(io::stdout().lock())
+                } else {
+                    Box::new(File::create(&options.output)?)
+                };
+                Sink::Y4m {
+                    writer: y4m::Y4mWriter::new(BufWriter::new(out), width, height, options.fps)?,
+                }
+            }
+        })
+    }
+
+    fn write_frame(&mut self, frame: u32, pixels: &[u8]) -> io::Result<()> {
+        match self {
+            Sink::Png {
+                directory,
+                width,
+                height,
+            } => fs::write(
+                directory.join(format!("frame-{frame:05}.png")),
+                png::encode_png(*width, *height, pixels)?,
+            ),
+            Sink::Gif { writer, fps } => {
+                // GIF 的延迟以百分之一秒计，按累计时间取整避免长动画漂移
+                let centiseconds =
+                    |frame: u32| (f64::from(frame) * 100.0 / f64::from(*fps)).round() as u64;
+                let delay = centiseconds(frame + 1) - centiseconds(frame);
+                writer.write_frame(pixels, delay.min(u64::from(u16::MAX)) as u16)
+            }
+            Sink::Y4m { writer } => writer.write_frame(pixels),
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Sink::Png { .. } => Ok(()),
+            Sink::Gif { writer, .. } => writer.finish()?.flush(),
+            Sink::Y4m { writer } => writer.finish()?.flush(),
+        }
+    }
+}
+
+fn parse_size(value: &str) -> Result<(u32, u32), String> {
+    let error = || format!("invalid size `{value}`, expected WIDTHxHEIGHT");
+    let (width, height) = value.split_once('x').ok_or_else(error)?;
+    let width: u32 = width.parse().map_err(|_| error())?;
+    let height: u32 = height.parse().map_err(|_| error())?;
+    if width == 0 || height == 0 {
+        return Err(error());
+    }
+    Ok((width, height))
+}
+
+fn parse_number(flag: &str, value: &str, min: f64, max: f64) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(number) if (min..=max).contains(&number) => Ok(number),
+        _ => Err(format!(
+            "{flag} must be a number between {min} and {max}, got `{value}`"
+        )),
+    }
+}
+
+fn parse_whole(flag: &str, value: &str, min: u32, max: u32) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(number) if (min..=max).contains(&number) => Ok(number),
+        _ => Err(format!(
+            "{flag} must be a whole number between {min} and {max}, got `{value}`"
+        )),
+    }
+}
+
+fn parse_color(value: &str) -> Result<[f64; 4], String> {
+    let error = || format!("invalid colour `{value}`, expected #rrggbb or #rrggbbaa");
+    let hex = value.strip_prefix('#').ok_or_else(error)?;
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return Err(error());
+    }
+    let mut color = [1.0; 4];
+    for (i, channel) in color.iter_mut().enumerate().take(hex.len() / 2) {
+        let byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| error())?;
+        *channel = f64::from(byte) / 255.0;
+    }
+    Ok(color)
+}
+
+fn parse_format(value: &str) -> Result<Format, String> {
+    match value {
+        "png" => Ok(Format::Png),
+        "gif" => Ok(Format::Gif),
+        "y4m" => Ok(Format::Y4m),
+        _ => Err(format!(
+            "unknown format `{value}`, expected png, gif or y4m"
+        )),
+    }
+}
@@ -0,0 +1,291 @@
+//! Minimal PNG encoder for RGBA8 frames, with its own deflate
+//! implementation. The integration tests include this file next to their
+//! decoder, which shares the checksums, filters and code tables.
+
+use std::io;
+
+pub(crate) const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Encodes RGBA8 `pixels` as a PNG file.
+pub fn encode_png(width: u32, height: u32, pixels: &[u8]) -> io::Result<Vec<u8>> {
+    if pixels.len() != width as usize * height as usize * 4 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "pixel data does not match the image size",
+        ));
+    }
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    // 8 位深度、RGBA、默认压缩与过滤方式、不隔行
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &ihdr);
+    write_chunk(
+        &mut png,
+        b"IDAT",
+        &zlib(&filter(width as usize * 4, pixels)),
+    );
+    write_chunk(&mut png, b"IEND", &[]);
+    Ok(png)
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], body: &[u8]) {
+    png.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(body);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Prefixes each row with the filter type that minimises the sum of
+/// absolute filtered values, the usual heuristic from the PNG spec.
+fn filter(stride: usize, pixels: &[u8]) -> Vec<u8> {
+    let rows = pixels.len() / stride.max(1);
+    let mut out = Vec::with_capacity((stride + 1) * rows);
+    let zero = vec![0; stride];
+    let mut candidate = vec![0; stride];
+    let mut best = vec![0; stride];
+
+    for row in 0..rows {
+        let line = &pixels[row * stride..(row + 1) * stride];
+        let above = if row > 0 {
+            &pixels[(row - 1) * stride..row * stride]
+        } else {
+            &zero
+        };
+
+        let mut best_kind = 0;
+        let mut best_score = u64::MAX;
+        for kind in 0..5 {
+            for i in 0..stride {
+                let left = if i >= 4 { line[i - 4] } else { 0 };
+                let up_left = if i >= 4 { above[i - 4] } else { 0 };
+                candidate[i] = line[i].wrapping_sub(predict(kind, left, above[i], up_left));
+            }
+            let score = candidate
+                .iter()
+                .map(|&v| u64::from((v as i8).unsigned_abs()))
+                .sum();
+            if score < best_score {
+                best_score = score;
+                best_kind = kind;
+                best.copy_from_slice(&candidate);
+            }
+        }
+
+        out.push(best_kind);
+        out.extend_from_slice(&best);
+    }
+    out
+}
+
+pub(crate) fn predict(kind: u8, left: u8, up: u8, up_left: u8) -> u8 {
+    match kind {
+        1 => left,
+        2 => up,
+        3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+        4 => {
+            let p = i16::from(left) + i16::from(up) - i16::from(up_left);
+            let (pa, pb, pc) = (
+                (p - i16::from(left)).abs(),
+                (p - i16::from(up)).abs(),
+                (p - i16::from(up_left)).abs(),
+            );
+            if pa <= pb && pa <= pc {
+                left
+            } else if pb <= pc {
+                up
+            } else {
+                up_left
+            }
+        }
+        _ => 0,
+    }
+}
+
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+// deflate 的长度码与距离码表（RFC 1951 第 3.2.5 节）
+pub(crate) const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+pub(crate) const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+pub(crate) const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+pub(crate) const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+const WINDOW: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: u32 = 15;
+
+struct BitWriter {
+    out: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, count: u32) {
+        self.bits |= u64::from(value) << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Writes a Huffman code, which deflate stores most significant bit
+    /// first.
+    fn write_code(&mut self, code: u32, length: u32) {
+        self.write(code.reverse_bits() >> (32 - length), length);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.out.push(self.bits as u8);
+        }
+        self.out
+    }
+}
+
+/// zlib stream using LZ77 with hash chains and the fixed Huffman codes.
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter {
+        out: vec![0x78, 0x01],
+        bits: 0,
+        count: 0,
+    };
+    writer.write(1, 1); // 最后一个块
+    writer.write(1, 2); // 固定霍夫曼编码
+
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; WINDOW];
+    let hash = |i: usize| {
+        let v = u32::from(data[i]) | u32::from(data[i + 1]) << 8 | u32::from(data[i + 2]) << 16;
+        (v.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+    };
+    let insert = |i: usize, head: &mut [usize], prev: &mut [usize]| {
+        if i + MIN_MATCH <= data.len() {
+            let h = hash(i);
+            prev[i % WINDOW] = head[h];
+            head[h] = i;
+        }
+    };
+
+    let mut i = 0;
+    while i < data.len() {
+        let mut best_length = 0;
+        let mut best_distance = 0;
+        if i + MIN_MATCH <= data.len() {
+            let mut candidate = head[hash(i)];
+            let limit = (data.len() - i).min(MAX_MATCH);
+            let mut chain = 0;
+            while candidate != usize::MAX && i - candidate <= WINDOW && chain < MAX_CHAIN {
+                let length = data[candidate..]
+                    .iter()
+                    .zip(&data[i..i + limit])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if length > best_length {
+                    best_length = length;
+                    best_distance = i - candidate;
+                    if length == limit {
+                        break;
+                    }
+                }
+                let next = prev[candidate % WINDOW];
+                if next == usize::MAX || next >= candidate {
+                    break;
+                }
+                candidate = next;
+                chain += 1;
+            }
+        }
+
+        if best_length >= MIN_MATCH {
+            write_length(&mut writer, best_length);
+            write_distance(&mut writer, best_distance);
+            for j in i..i + best_length {
+                insert(j, &mut head, &mut prev);
+            }
+            i += best_length;
+        } else {
+            write_literal(&mut writer, u32::from(data[i]));
+            insert(i, &mut head, &mut prev);
+            i += 1;
+        }
+    }
+    write_literal(&mut writer, 256);
+
+    let mut out = writer.finish();
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn write_literal(writer: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => writer.write_code(0x30 + symbol, 8),
+        144..=255 => writer.write_code(0x190 + symbol - 144, 9),
+        256..=279 => writer.write_code(symbol - 256, 7),
+        _ => writer.write_code(0xc0 + symbol - 280, 8),
+    }
+}
+
+fn write_length(writer: &mut BitWriter, length: usize) {
+    let code = LENGTH_BASE.partition_point(|&base| usize::from(base) <= length) - 1;
+    write_literal(writer, 257 + code as u32);
+    let extra = u32::from(LENGTH_EXTRA[code]);
+    if extra > 0 {
+        writer.write((length - usize::from(LENGTH_BASE[code])) as u32, extra);
+    }
+}
+
+fn write_distance(writer: &mut BitWriter, distance: usize) {
+    let code = DISTANCE_BASE.partition_point(|&base| usize::from(base) <= distance) - 1;
+    writer.write_code(code as u32, 5);
+    let extra = u32::from(DISTANCE_EXTRA[code]);
+    if extra > 0 {
+        writer.write((distance - usize::from(DISTANCE_BASE[code])) as u32, extra);
+    }
+}
@@ -0,0 +1,39 @@
+use std::io::{self, Write};
+
+/// Uncompressed YUV4MPEG2 stream with full-resolution (4:4:4) chroma, as
+/// read by `ffmpeg -i frames.y4m`.
+pub struct Y4mWriter<W: Write> {
+    out: W,
+    planes: Vec<u8>,
+}
+
+impl<W: Write> Y4mWriter<W> {
+    pub fn new(mut out: W, width: u32, height: u32, fps: u32) -> io::Result<Y4mWriter<W>> {
+        writeln!(out, "YUV4MPEG2 W{width} H{height} F{fps}:1 Ip A1:1 C444")?;
+        Ok(Y4mWriter {
+            out,
+            planes: vec![0; width as usize * height as usize * 3],
+        })
+    }
+
+    /// Writes an RGBA8 frame, compositing transparent pixels over black.
+    pub fn write_frame(&mut self, pixels: &[u8]) -> io::Result<()> {
+        let area = pixels.len() / 4;
+        let (y_plane, chroma) = self.planes.split_at_mut(area);
+        let (u_plane, v_plane) = chroma.split_at_mut(area);
+        for (i, pixel) in pixels.chunks_exact(4).enumerate() {
+            let alpha = f64::from(pixel[3]) / 255.0;
+            let [r, g, b] = [pixel[0], pixel[1], pixel[2]].map(|c| f64::from(c) / 255.0 * alpha);
+            // BT.601 有限范围（16–235），ffmpeg 对 Y4M 的默认解释
+            y_plane[i] = (16.0 + 65.481 * r + 128.553 * g + 24.966 * b).round() as u8;
+            u_plane[i] = (128.0 - 37.797 * r - 74.203 * g + 112.0 * b).round() as u8;
+            v_plane[i] = (128.0 + 112.0 * r - 93.786 * g - 18.214 * b).round() as u8;
+        }
+        self.out.write_all(b"FRAME\n")?;
+        self.out.write_all(&self.planes)
+    }
+
+    pub fn finish(self) -> io::Result<W> {
+        Ok(self.out)
+    }
+}
@@ -0,0 +1,146 @@
+use crate::rng::Xoshiro256;
+use crate::{random_particle, Particle, ParticleRanges};
+use rand::Rng;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// What happens to a particle that drifts past the edge of the canvas.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoundaryMode {
+    /// Reflect off the edge, scaled by `border_restitution`.
+    Bounce = 0,
+    /// Re-enter from the opposite edge; connections are drawn across edges.
+    Wrap = 1,
+    /// Stop at the edge.
+    Clamp = 2,
+    /// Re-enter from a random point on the edge, heading inwards.
+    Respawn = 3,
+    /// Disappear and be replaced by a fresh particle somewhere inside.
+    Absorb = 4,
+}
+
+/// Returns `true` when the particle was replaced by a fresh one.
+pub(crate) fn apply(
+    mode: BoundaryMode,
+    particle: &mut Particle,
+    width: f64,
+    height: f64,
+    restitution: f64,
+    ranges: &ParticleRanges,
+    rng: &mut Xoshiro256,
+) -> bool {
+    match mode {
+        BoundaryMode::Bounce => bounce(particle, width, height, restitution),
+        BoundaryMode::Wrap => wrap(particle, width, height),
+        BoundaryMode::Clamp => {
+            particle.x = particle.x.clamp(0.0, width.max(0.0));
+            particle.y = particle.y.clamp(0.0, height.max(0.0));
+        }
+        BoundaryMode::Respawn => {
+            if is_outside(particle, width, height) {
+                respawn_on_edge(particle, width, height, rng);
+            }
+        }
+        BoundaryMode::Absorb => {
+            if is_outside(particle, width, height) {
+                if particle.lifetime_ms.is_finite() {
+                    // 有寿命的粒子直接结束，不补充新粒子
+                    particle.age_ms = particle.lifetime_ms;
+                    return false;
+                }
+                *particle = Particle {
+                    id: particle.id,
+                    ..random_particle(rng, width, height, ranges)
+                };
+                return true;
+            }
+        }
+    }
+    false
+}
+
+fn is_outside(particle: &Particle, width: f64, height: f64) -> bool {
+    particle.x < 0.0 || particle.x > width || particle.y < 0.0 || particle.y > height
+}
+
+fn bounce(particle: &mut Particle, width: f64, height: f64, restitution: f64) {
+    // 同时反射当前速度，惯性模式下粒子不会继续贴着边缘向外推
+    if particle.x < 0.0 {
+        particle.x = 0.0;
+        particle.base_vx = particle.base_vx.abs() * restitution;
+        particle.target_vx = particle.target_vx.abs() * restitution;
+        particle.vx = particle.vx.abs() * restitution;
+    } else if particle.x > width {
+        particle.x = width;
+        particle.base_vx = -particle.base_vx.abs() * restitution;
+        particle.target_vx = -particle.target_vx.abs() * restitution;
+        particle.vx = -particle.vx.abs() * restitution;
+    }
+
+    if particle.y < 0.0 {
+        particle.y = 0.0;
+        particle.base_vy = particle.base_vy.abs() * restitution;
+        particle.target_vy = particle.target_vy.abs() * restitution;
+        particle.vy = particle.vy.abs() * restitution;
+    } else if particle.y > height {
+        particle.y = height;
+        particle.base_vy = -particle.base_vy.abs() * restitution;
+        particle.target_vy = -particle.target_vy.abs() * restitution;
+        particle.vy = -particle.vy.abs() * restitution;
+    }
+}
+
+fn wrap(particle: &mut Particle, width: f64, height: f64) {
+    // 同步平移上一帧位置，避免插值时从一端划到另一端
+    if width > 0.0 && (particle.x < 0.0 || particle.x >= width) {
+        let wrapped = particle.x.rem_euclid(width);
+        particle.prev_x += wrapped - particle.x;
+        particle.x = wrapped;
+    }
+    if height > 0.0 && (particle.y < 0.0 || particle.y >= height) {
+        let wrapped = particle.y.rem_euclid(height);
+        particle.prev_y += wrapped - particle.y;
+        particle.y = wrapped;
+    }
+}
+
+fn respawn_on_edge(particle: &mut Particle, width: f64, height: f64, rng: &mut Xoshiro256) {
+    let width = width.max(0.0);
+    let height = height.max(0.0);
+    let perimeter = 2.0 * (width + height);
+    let t = if perimeter > 0.0 {
+        rng.gen_range(0.0..perimeter)
+    } else {
+        0.0
+    };
+
+    if t < width {
+        particle.x = t;
+        particle.y = 0.0;
+        particle.base_vy = particle.base_vy.abs();
+        particle.target_vy = particle.target_vy.abs();
+    } else if t < width + height {
+        particle.x = width;
+        particle.y = t - width;
+        particle.base_vx = -particle.base_vx.abs();
+        particle.target_vx = -particle.target_vx.abs();
+    } else if t < 2.0 * width + height {
+        particle.x = t - width - height;
+        particle.y = height;
+        particle.base_vy = -particle.base_vy.abs();
+        particle.target_vy = -particle.target_vy.abs();
+    } else {
+        particle.x = 0.0;
+        particle.y = t - 2.0 * width - height;
+        particle.base_vx = particle.base_vx.abs();
+        particle.target_vx = particle.target_vx.abs();
+    }
+
+    particle.prev_x = particle.x;
+    particle.prev_y = particle.y;
+    particle.vx = particle.base_vx;
+    particle.vy = particle.base_vy;
+    particle.is_orbiting = false;
+    particle.orbit_blend = 0.0;
+}
@@ -0,0 +1,265 @@
+use crate::connections::{self, Link, LinkScratch};
+use crate::layout::{INSTANCE_FLOATS, LINE_VERTEX_FLOATS};
+use crate::{ConnectionFormat, ParticleSystem};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Per-frame output owned by the system and reused between frames, so JS
+/// can read it straight out of wasm memory.
+#[derive(Default)]
+pub(crate) struct FrameBuffers {
+    particles: Vec<f32>,
+    ids: Vec<u32>,
+    connections: Vec<f32>,
+    connection_indices: Vec<u32>,
+    connection_opacities: Vec<f32>,
+    mouse_connections: Vec<f32>,
+    instances: Vec<f32>,
+    line_vertices: Vec<f32>,
+    links: Vec<Link>,
+    scratch: LinkScratch,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ParticleSystem {
+    /// Fills the frame buffers: particles as `x, y, size` plus any enabled
+    /// `ParticleField`s, with their ids in a parallel `u32` buffer,
+    /// connections as `x1, y1, x2, y2, opacity` (plus `r, g, b, a` with
+    /// colours enabled) or as index pairs and opacities, depending on
+    /// `connection_format`, and mouse connections as `x, y, strength`.
+    /// With `interpolated` set, positions are blended by
+    /// `interpolation_alpha` like `get_interpolated_particles`. With
+    /// `webgl_output` on, the interleaved instance and line vertex buffers
+    /// are filled as well.
+    ///
+    /// Pointers and views into the buffers are invalidated by the next call
+    /// and by any growth of wasm memory.
+    pub fn prepare_frame(&mut self, interpolated: bool) {
+        let alpha = if interpolated { self.alpha } else { 1.0 };
+        let mut buffers = std::mem::take(&mut self.buffers);
+
+        buffers.particles.clear();
+        buffers.ids.clear();
+        for particle in &self.particles {
+            buffers.ids.push(particle.id);
+            let position = particle.position_at(alpha);
+            self.write_particle(particle, position, |value| {
+                buffers.particles.push(value as f32);
+            });
+        }
+
+        self.collect_links(&mut buffers.scratch, &mut buffers.links);
+        buffers.connections.clear();
+        buffers.connection_indices.clear();
+        buffers.connection_opacities.clear();
+        if self.connection_format == ConnectionFormat::Indexed {
+            connections::write_indexed(
+                &buffers.links,
+                &mut buffers.connection_indices,
+                &mut buffers.connection_opacities,
+            );
+            // 跨越边缘的连接线无法用两个粒子索引表示，改为线段写入连接缓冲区
+            let crossing = buffers.links.iter().filter(|link| link.crosses_edge);
+            self.write_segments(alpha, crossing, &mut buffers.connections);
+        } else {
+            self.write_segments(alpha, buffers.links.iter(), &mut buffers.connections);
+        }
+
+        buffers.instances.clear();
+        buffers.line_vertices.clear();
+        if self.webgl_output {
+            self.write_instances(alpha, &mut buffers.instances);
+            self.write_line_vertices(alpha, &buffers.links, &mut buffers.line_vertices);
+        }
+
+        buffers.mouse_connections.clear();
+        for &(idx, strength) in &self.mouse_connections {
+            let particle = &self.particles[idx];
+            if !self.mouse_connection_visible(particle) {
+                continue;
+            }
+            let (x, y) = particle.position_at(alpha);
+            buffers
+                .mouse_connections
+                .extend_from_slice(&[x as f32, y as f32, strength as f32]);
+        }
+
+        self.buffers = buffers;
+    }
+
+    pub fn webgl_output(&self) -> bool {
+        self.webgl_output
+    }
+
+    /// Opts in to the interleaved `f32` instance and line vertex buffers
+    /// described by `instance_layout` and `line_vertex_layout`.
+    pub fn set_webgl_output(&mut self, enabled: bool) {
+        self.webgl_output = enabled;
+    }
+
+    pub fn particles_ptr(&self) -> *const f32 {
+        self.buffers.particles.as_ptr()
+    }
+
+    pub fn particles_len(&self) -> usize {
+        self.buffers.particles.len()
+    }
+
+    pub fn ids_ptr(&self) -> *const u32 {
+        self.buffers.ids.as_ptr()
+    }
+
+    pub fn ids_len(&self) -> usize {
+        self.buffers.ids.len()
+    }
+
+    pub fn connections_ptr(&self) -> *const f32 {
+        self.buffers.connections.as_ptr()
+    }
+
+    pub fn connections_len(&self) -> usize {
+        self.buffers.connections.len()
+    }
+
+    pub fn connection_indices_ptr(&self) -> *const u32 {
+        self.buffers.connection_indices.as_ptr()
+    }
+
+    pub fn connection_indices_len(&self) -> usize {
+        self.buffers.connection_indices.len()
+    }
+
+    pub fn connection_opacities_ptr(&self) -> *const f32 {
+        self.buffers.connection_opacities.as_ptr()
+    }
+
+    pub fn connection_opacities_len(&self) -> usize {
+        self.buffers.connection_opacities.len()
+    }
+
+    pub fn instances_ptr(&self) -> *const f32 {
+        self.buffers.instances.as_ptr()
+    }
+
+    pub fn instances_len(&self) -> usize {
+        self.buffers.instances.len()
+    }
+
+    pub fn line_vertices_ptr(&self) -> *const f32 {
+        self.buffers.line_vertices.as_ptr()
+    }
+
+    pub fn line_vertices_len(&self) -> usize {
+        self.buffers.line_vertices.len()
+    }
+
+    pub fn mouse_connections_ptr(&self) -> *const f32 {
+        self.buffers.mouse_connections.as_ptr()
+    }
+
+    pub fn mouse_connections_len(&self) -> usize {
+        self.buffers.mouse_connections.len()
+    }
+}
+
+impl ParticleSystem {
+    pub fn particle_buffer(&self) -> &[f32] {
+        &self.buffers.particles
+    }
+
+    pub fn id_buffer(&self) -> &[u32] {
+        &self.buffers.ids
+    }
+
+    pub fn connection_buffer(&self) -> &[f32] {
+        &self.buffers.connections
+    }
+
+    pub fn connection_index_buffer(&self) -> &[u32] {
+        &self.buffers.connection_indices
+    }
+
+    pub fn connection_opacity_buffer(&self) -> &[f32] {
+        &self.buffers.connection_opacities
+    }
+
+    pub fn mouse_connection_buffer(&self) -> &[f32] {
+        &self.buffers.mouse_connections
+    }
+
+    pub fn instance_buffer(&self) -> &[f32] {
+        &self.buffers.instances
+    }
+
+    pub fn line_vertex_buffer(&self) -> &[f32] {
+        &self.buffers.line_vertices
+    }
+
+    fn write_instances(&self, alpha: f64, out: &mut Vec<f32>) {
+        out.reserve(self.particles.len() * INSTANCE_FLOATS);
+        for particle in &self.particles {
+            let (x, y) = particle.position_at(alpha);
+            let [r, g, b, a] = self.color_of(particle);
+            out.extend_from_slice(&[
+                x as f32,
+                y as f32,
+                particle.size as f32,
+                r as f32,
+                g as f32,
+                b as f32,
+                (a * particle.fade()) as f32,
+            ]);
+        }
+    }
+
+    fn write_segments<'a>(
+        &self,
+        alpha: f64,
+        links: impl Iterator<Item = &'a Link>,
+        out: &mut Vec<f32>,
+    ) {
+        for link in links {
+            let a = self.particles[link.a].position_at(alpha);
+            let b = self.particles[link.b].position_at(alpha);
+            let color = self.link_color(link);
+            link.segments(a, b, |[x1, y1, x2, y2]| {
+                out.extend_from_slice(&[
+                    x1 as f32,
+                    y1 as f32,
+                    x2 as f32,
+                    y2 as f32,
+                    link.opacity as f32,
+                ]);
+                if let Some([r, g, b, a]) = color {
+                    out.extend_from_slice(&[r as f32, g as f32, b as f32, a as f32]);
+                }
+            });
+        }
+    }
+
+    fn write_line_vertices(&self, alpha: f64, links: &[Link], out: &mut Vec<f32>) {
+        out.reserve(links.len() * 2 * LINE_VERTEX_FLOATS);
+        for link in links {
+            let p1 = &self.particles[link.a];
+            let p2 = &self.particles[link.b];
+            let c1 = self.color_of(p1);
+            let c2 = self.color_of(p2);
+            link.segments(
+                p1.position_at(alpha),
+                p2.position_at(alpha),
+                |[x1, y1, x2, y2]| {
+                    for (x, y, [r, g, b, a]) in [(x1, y1, c1), (x2, y2, c2)] {
+                        out.extend_from_slice(&[
+                            x as f32,
+                            y as f32,
+                            r as f32,
+                            g as f32,
+                            b as f32,
+                            (a * link.opacity) as f32,
+                        ]);
+                    }
+                },
+            );
+        }
+    }
+}
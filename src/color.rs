@@ -0,0 +1,258 @@
+use crate::params::{self, Range};
+use crate::{Particle, ParticleError, ParticleSystem};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+pub(crate) type Rgba = [f64; 4];
+
+/// Which rule decides a particle's colour.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    /// A palette entry picked when the particle is spawned.
+    Palette = 0,
+    /// The gradient, sampled by speed within `speed_range`.
+    Speed = 1,
+    /// The gradient, sampled by how strongly the mouse pulls the particle.
+    MouseAttraction = 2,
+}
+
+/// Colour rules, with every component in `0..=1`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorRules {
+    pub mode: ColorMode,
+    pub palette: Vec<[f64; 4]>,
+    /// `(position, colour)` stops with positions ascending in `0..=1`.
+    pub gradient: Vec<(f64, [f64; 4])>,
+    /// Speeds, in pixels per step, mapped to the ends of the gradient.
+    pub speed_range: Range,
+}
+
+impl Default for ColorRules {
+    fn default() -> ColorRules {
+        ColorRules {
+            mode: ColorMode::Palette,
+            palette: vec![[1.0, 1.0, 1.0, 1.0]],
+            gradient: vec![(0.0, [0.4, 0.6, 1.0, 1.0]), (1.0, [1.0, 1.0, 1.0, 1.0])],
+            speed_range: Range::new(0.0, 2.0),
+        }
+    }
+}
+
+impl ColorRules {
+    pub fn validate(&self) -> Result<(), ParticleError> {
+        if self.palette.is_empty() {
+            return Err(empty("color.palette"));
+        }
+        for color in &self.palette {
+            components("color.palette", color)?;
+        }
+
+        if self.gradient.is_empty() {
+            return Err(empty("color.gradient"));
+        }
+        let mut last = 0.0;
+        for (position, color) in &self.gradient {
+            let position = params::fraction("color.gradient position", *position)?;
+            if position < last {
+                return Err(ParticleError::InvalidValue {
+                    name: "color.gradient position",
+                    value: position,
+                    expected: "in ascending order",
+                });
+            }
+            last = position;
+            components("color.gradient", color)?;
+        }
+
+        self.speed_range.validate("color.speed_range")?;
+        Ok(())
+    }
+
+    fn pick(&self, id: u32, seed: u64) -> Rgba {
+        // 由种子和 ID 决定，不占用随机数序列，调色板更换后已有粒子也能重新分配
+        let index = mix(seed ^ u64::from(id)) % self.palette.len() as u64;
+        self.palette[index as usize]
+    }
+
+    fn sample(&self, t: f64) -> Rgba {
+        let t = t.clamp(0.0, 1.0);
+        let after = self.gradient.partition_point(|&(position, _)| position < t);
+        if after == 0 {
+            return self.gradient[0].1;
+        }
+        if after == self.gradient.len() {
+            return self.gradient[after - 1].1;
+        }
+
+        let (p0, c0) = self.gradient[after - 1];
+        let (p1, c1) = self.gradient[after];
+        let f = if p1 > p0 { (t - p0) / (p1 - p0) } else { 1.0 };
+        blend(c0, c1, f)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ParticleSystem {
+    pub fn color_mode(&self) -> ColorMode {
+        self.colors.mode
+    }
+
+    pub fn set_color_mode(&mut self, mode: ColorMode) {
+        self.colors.mode = mode;
+    }
+
+    /// Palette as flat `r, g, b, a` records.
+    pub fn palette(&self) -> Vec<f64> {
+        self.colors.palette.iter().flatten().copied().collect()
+    }
+
+    /// Replaces the palette from flat `r, g, b, a` records. Existing
+    /// particles are reassigned to the new entries.
+    pub fn set_palette(&mut self, colors: Vec<f64>) -> Result<(), ParticleError> {
+        if !colors.len().is_multiple_of(4) {
+            return Err(record_length(
+                "color.palette length",
+                colors.len(),
+                "a multiple of 4",
+            ));
+        }
+        let palette = colors
+            .chunks_exact(4)
+            .map(|c| [c[0], c[1], c[2], c[3]])
+            .collect();
+        self.replace_colors(ColorRules {
+            palette,
+            ..self.colors.clone()
+        })
+    }
+
+    /// Gradient as flat `position, r, g, b, a` records.
+    pub fn color_gradient(&self) -> Vec<f64> {
+        let mut stops = Vec::with_capacity(self.colors.gradient.len() * 5);
+        for (position, color) in &self.colors.gradient {
+            stops.push(*position);
+            stops.extend_from_slice(color);
+        }
+        stops
+    }
+
+    /// Replaces the gradient from flat `position, r, g, b, a` records with
+    /// positions ascending in `0..=1`.
+    pub fn set_color_gradient(&mut self, stops: Vec<f64>) -> Result<(), ParticleError> {
+        if !stops.len().is_multiple_of(5) {
+            return Err(record_length(
+                "color.gradient length",
+                stops.len(),
+                "a multiple of 5",
+            ));
+        }
+        let gradient = stops
+            .chunks_exact(5)
+            .map(|s| (s[0], [s[1], s[2], s[3], s[4]]))
+            .collect();
+        self.replace_colors(ColorRules {
+            gradient,
+            ..self.colors.clone()
+        })
+    }
+
+    pub fn color_speed_range(&self) -> Vec<f64> {
+        vec![self.colors.speed_range.min, self.colors.speed_range.max]
+    }
+
+    pub fn set_color_speed_range(&mut self, min: f64, max: f64) -> Result<(), ParticleError> {
+        self.colors.speed_range = Range::new(min, max).validate("color.speed_range")?;
+        Ok(())
+    }
+
+    /// The particle's current colour as `r, g, b, a`, or an empty array for
+    /// an unknown id.
+    pub fn particle_color(&self, id: u32) -> Vec<f64> {
+        match self.ids.lookup(id) {
+            Some(index) => self.color_of(&self.particles[index]).to_vec(),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl ParticleSystem {
+    pub fn color_rules(&self) -> &ColorRules {
+        &self.colors
+    }
+
+    pub fn replace_colors(&mut self, colors: ColorRules) -> Result<(), ParticleError> {
+        colors.validate()?;
+        self.colors = colors;
+        Ok(())
+    }
+
+    pub(crate) fn color_of(&self, particle: &Particle) -> Rgba {
+        match self.colors.mode {
+            ColorMode::Palette => self.colors.pick(particle.id, self.seed),
+            ColorMode::Speed => {
+                let range = self.colors.speed_range;
+                let speed = particle.vx.hypot(particle.vy);
+                let t = if range.max > range.min {
+                    (speed - range.min) / (range.max - range.min)
+                } else if speed >= range.max {
+                    1.0
+                } else {
+                    0.0
+                };
+                self.colors.sample(t)
+            }
+            ColorMode::MouseAttraction => {
+                let mut t = 0.0;
+                if self.mouse_active() && self.mouse_radius > 0.0 {
+                    let distance = (particle.x - self.mouse_x).hypot(particle.y - self.mouse_y);
+                    if distance < self.mouse_radius {
+                        let edge_factor = 1.0 - distance / self.mouse_radius;
+                        t = edge_factor * edge_factor;
+                    }
+                }
+                self.colors.sample(t)
+            }
+        }
+    }
+}
+
+pub(crate) fn blend(a: Rgba, b: Rgba, t: f64) -> Rgba {
+    [
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+        a[3] + (b[3] - a[3]) * t,
+    ]
+}
+
+// SplitMix64 的最终混合步骤
+pub(crate) fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn components(name: &'static str, color: &Rgba) -> Result<(), ParticleError> {
+    for &component in color {
+        params::fraction(name, component)?;
+    }
+    Ok(())
+}
+
+fn empty(name: &'static str) -> ParticleError {
+    ParticleError::InvalidValue {
+        name,
+        value: 0.0,
+        expected: "at least one entry",
+    }
+}
+
+fn record_length(name: &'static str, len: usize, expected: &'static str) -> ParticleError {
+    ParticleError::InvalidValue {
+        name,
+        value: len as f64,
+        expected,
+    }
+}
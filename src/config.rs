@@ -0,0 +1,670 @@
+use crate::json::Json;
+use crate::params::{self, Range};
+use crate::{
+    physics, string_seed, BoundaryMode, ColorMode, ColorRules, MotionModel, ParticleError,
+    ParticleRanges, ParticleSystem, FIXED_STEP_MS, MAX_CATCH_UP_STEPS,
+};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+pub const PRESET_NAMES: [&str; 4] = ["default", "constellation", "snow", "swarm"];
+
+/// Everything needed to construct a `ParticleSystem`, serializable to and
+/// from JSON. Fields missing from a JSON document keep their defaults, or
+/// the values of the preset named by its `"preset"` key.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParticleConfig {
+    pub width: f64,
+    pub height: f64,
+    pub particle_count: usize,
+    pub connection_distance: f64,
+    /// Nearest connections kept per particle; `0` for no limit.
+    pub max_connections_per_particle: usize,
+    /// Connection lines drawn per frame, most opaque first; `0` for no limit.
+    pub max_connections: usize,
+    /// Seed for the layout; `None` picks a random one on construction.
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub seed: Option<u64>,
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub ranges: ParticleRanges,
+    pub mouse_radius: f64,
+    pub mouse_force: f64,
+    pub max_attraction_force: f64,
+    pub boundary_mode: BoundaryMode,
+    pub border_restitution: f64,
+    pub motion_model: MotionModel,
+    pub linear_damping: f64,
+    pub velocity_relaxation: f64,
+    pub max_speed: f64,
+    pub orbit_enabled: bool,
+    pub fixed_timestep_ms: f64,
+    pub max_catch_up_steps: u32,
+    /// Length of the seamless loop; `0` for free-running physics.
+    pub loop_period_ms: f64,
+    #[cfg_attr(feature = "wasm", wasm_bindgen(skip))]
+    pub color: ColorRules,
+}
+
+impl Default for ParticleConfig {
+    fn default() -> ParticleConfig {
+        ParticleConfig {
+            width: 1280.0,
+            height: 720.0,
+            particle_count: 100,
+            connection_distance: 120.0,
+            max_connections_per_particle: 0,
+            max_connections: 0,
+            seed: None,
+            ranges: ParticleRanges::default(),
+            mouse_radius: 150.0,
+            mouse_force: 1.0,
+            max_attraction_force: 0.4,
+            boundary_mode: BoundaryMode::Bounce,
+            border_restitution: 1.0,
+            motion_model: MotionModel::Kinematic,
+            linear_damping: physics::DEFAULT_LINEAR_DAMPING,
+            velocity_relaxation: physics::DEFAULT_VELOCITY_RELAXATION,
+            max_speed: physics::DEFAULT_MAX_SPEED,
+            orbit_enabled: true,
+            fixed_timestep_ms: FIXED_STEP_MS,
+            max_catch_up_steps: MAX_CATCH_UP_STEPS,
+            loop_period_ms: 0.0,
+            color: ColorRules::default(),
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ParticleConfig {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> ParticleConfig {
+        ParticleConfig::default()
+    }
+
+    /// One of the built-in starting points: `"default"`, `"constellation"`,
+    /// `"snow"` or `"swarm"`.
+    pub fn preset(name: &str) -> Result<ParticleConfig, ParticleError> {
+        let defaults = ParticleConfig::default();
+        let config = match name {
+            "default" => defaults,
+            "constellation" => ParticleConfig {
+                particle_count: 80,
+                connection_distance: 160.0,
+                ranges: ParticleRanges {
+                    velocity_x: Range::new(-0.15, 0.15),
+                    velocity_y: Range::new(-0.15, 0.15),
+                    size: Range::new(0.8, 2.2),
+                    ..defaults.ranges
+                },
+                mouse_radius: 200.0,
+                mouse_force: 0.6,
+                boundary_mode: BoundaryMode::Wrap,
+                ..defaults
+            },
+            "snow" => ParticleConfig {
+                particle_count: 220,
+                connection_distance: 0.0,
+                ranges: ParticleRanges {
+                    velocity_x: Range::new(-0.3, 0.3),
+                    velocity_y: Range::new(0.4, 1.2),
+                    size: Range::new(1.5, 4.0),
+                    ..defaults.ranges
+                },
+                mouse_radius: 100.0,
+                mouse_force: -1.0,
+                boundary_mode: BoundaryMode::Wrap,
+                orbit_enabled: false,
+                ..defaults
+            },
+            "swarm" => ParticleConfig {
+                particle_count: 300,
+                connection_distance: 60.0,
+                ranges: ParticleRanges {
+                    size: Range::new(0.8, 1.8),
+                    ..defaults.ranges
+                },
+                mouse_radius: 250.0,
+                mouse_force: 2.0,
+                max_attraction_force: 0.8,
+                motion_model: MotionModel::Inertial,
+                linear_damping: 0.02,
+                velocity_relaxation: 0.02,
+                max_speed: 5.0,
+                ..defaults
+            },
+            _ => return Err(ParticleError::UnknownPreset(name.to_string())),
+        };
+        Ok(config)
+    }
+
+    pub fn preset_names() -> Vec<String> {
+        PRESET_NAMES.iter().map(|name| name.to_string()).collect()
+    }
+
+    pub fn from_json(text: &str) -> Result<ParticleConfig, ParticleError> {
+        let json = Json::parse(text).map_err(|error| ParticleError::Json {
+            line: error.line,
+            column: error.column,
+            message: error.message,
+        })?;
+        let config = ParticleConfig::from_json_value(&json)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn to_json(&self) -> String {
+        self.to_json_value().to_pretty_string()
+    }
+
+    pub fn validate(&self) -> Result<(), ParticleError> {
+        params::positive("width", self.width)?;
+        params::positive("height", self.height)?;
+        if self.particle_count > crate::ids::MAX_PARTICLES {
+            return Err(ParticleError::TooManyParticles);
+        }
+        params::non_negative("connection_distance", self.connection_distance)?;
+        self.ranges.validate()?;
+        params::non_negative("mouse.radius", self.mouse_radius)?;
+        params::finite("mouse.force", self.mouse_force)?;
+        params::non_negative("mouse.max_attraction_force", self.max_attraction_force)?;
+        params::non_negative("boundary.restitution", self.border_restitution)?;
+        params::fraction("motion.linear_damping", self.linear_damping)?;
+        params::fraction("motion.velocity_relaxation", self.velocity_relaxation)?;
+        params::positive("motion.max_speed", self.max_speed)?;
+        params::positive("timestep.fixed_ms", self.fixed_timestep_ms)?;
+        if self.max_catch_up_steps == 0 {
+            return Err(ParticleError::InvalidValue {
+                name: "timestep.max_catch_up_steps",
+                value: 0.0,
+                expected: "at least 1",
+            });
+        }
+        params::non_negative("timestep.loop_period_ms", self.loop_period_ms)?;
+        self.color.validate()?;
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(setter))]
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    /// Decimal digits are taken as the seed itself and any other text is
+    /// hashed, as for a string `seed` in JSON.
+    pub fn set_string_seed(&mut self, seed: &str) {
+        self.seed = Some(string_seed(seed));
+    }
+}
+
+impl ParticleConfig {
+    pub(crate) fn from_json_value(json: &Json) -> Result<ParticleConfig, ParticleError> {
+        let fields = object(json, "config")?;
+
+        let mut config = match json.get("preset") {
+            Some(name) => ParticleConfig::preset(string(name, "preset")?)?,
+            None => ParticleConfig::default(),
+        };
+
+        for (key, value) in fields {
+            match key.as_str() {
+                "preset" => {}
+                "width" => config.width = number(value, "width")?,
+                "height" => config.height = number(value, "height")?,
+                "particle_count" => config.particle_count = count(value, "particle_count")?,
+                "connection_distance" => {
+                    config.connection_distance = number(value, "connection_distance")?
+                }
+                "connection_limits" => {
+                    for (key, value) in object(value, "connection_limits")? {
+                        match key.as_str() {
+                            "per_particle" => {
+                                config.max_connections_per_particle =
+                                    count(value, "connection_limits.per_particle")?
+                            }
+                            "per_frame" => {
+                                config.max_connections =
+                                    count(value, "connection_limits.per_frame")?
+                            }
+                            _ => return Err(unknown_field("connection_limits", key)),
+                        }
+                    }
+                }
+                "seed" => config.seed = seed(value)?,
+                "ranges" => config.ranges = ranges(value, config.ranges)?,
+                "mouse" => {
+                    for (key, value) in object(value, "mouse")? {
+                        match key.as_str() {
+                            "radius" => config.mouse_radius = number(value, "mouse.radius")?,
+                            "force" => config.mouse_force = number(value, "mouse.force")?,
+                            "max_attraction_force" => {
+                                config.max_attraction_force =
+                                    number(value, "mouse.max_attraction_force")?
+                            }
+                            _ => return Err(unknown_field("mouse", key)),
+                        }
+                    }
+                }
+                "boundary" => {
+                    for (key, value) in object(value, "boundary")? {
+                        match key.as_str() {
+                            "mode" => {
+                                config.boundary_mode =
+                                    parse_boundary_mode(string(value, "boundary.mode")?)?
+                            }
+                            "restitution" => {
+                                config.border_restitution = number(value, "boundary.restitution")?
+                            }
+                            _ => return Err(unknown_field("boundary", key)),
+                        }
+                    }
+                }
+                "motion" => {
+                    for (key, value) in object(value, "motion")? {
+                        match key.as_str() {
+                            "model" => {
+                                config.motion_model =
+                                    parse_motion_model(string(value, "motion.model")?)?
+                            }
+                            "linear_damping" => {
+                                config.linear_damping = number(value, "motion.linear_damping")?
+                            }
+                            "velocity_relaxation" => {
+                                config.velocity_relaxation =
+                                    number(value, "motion.velocity_relaxation")?
+                            }
+                            "max_speed" => config.max_speed = number(value, "motion.max_speed")?,
+                            _ => return Err(unknown_field("motion", key)),
+                        }
+                    }
+                }
+                "orbit_enabled" => config.orbit_enabled = boolean(value, "orbit_enabled")?,
+                "timestep" => {
+                    for (key, value) in object(value, "timestep")? {
+                        match key.as_str() {
+                            "fixed_ms" => {
+                                config.fixed_timestep_ms = number(value, "timestep.fixed_ms")?
+                            }
+                            "max_catch_up_steps" => {
+                                config.max_catch_up_steps =
+                                    count(value, "timestep.max_catch_up_steps")?
+                                        .try_into()
+                                        .map_err(|_| {
+                                            config_error(
+                                                "timestep.max_catch_up_steps",
+                                                "is too large",
+                                            )
+                                        })?
+                            }
+                            "loop_period_ms" => {
+                                config.loop_period_ms = number(value, "timestep.loop_period_ms")?
+                            }
+                            _ => return Err(unknown_field("timestep", key)),
+                        }
+                    }
+                }
+                "color" => config.color = color(value, config.color)?,
+                _ => return Err(unknown_field("", key)),
+            }
+        }
+
+        Ok(config)
+    }
+
+    pub(crate) fn to_json_value(&self) -> Json {
+        let range = |range: Range| Json::Array(vec![range.min.into(), range.max.into()]);
+
+        let mut fields = vec![
+            ("width", self.width.into()),
+            ("height", self.height.into()),
+            ("particle_count", (self.particle_count as f64).into()),
+            ("connection_distance", self.connection_distance.into()),
+            (
+                "connection_limits",
+                Json::object(vec![
+                    (
+                        "per_particle",
+                        (self.max_connections_per_particle as f64).into(),
+                    ),
+                    ("per_frame", (self.max_connections as f64).into()),
+                ]),
+            ),
+        ];
+        if let Some(seed) = self.seed {
+            // 超过 2^53 的种子无法用 JSON 数字精确表示，改为字符串
+            let seed = if seed <= MAX_SAFE_INTEGER {
+                Json::Number(seed as f64)
+            } else {
+                Json::String(seed.to_string())
+            };
+            fields.push(("seed", seed));
+        }
+        fields.extend([
+            (
+                "ranges",
+                Json::object(vec![
+                    ("velocity_x", range(self.ranges.velocity_x)),
+                    ("velocity_y", range(self.ranges.velocity_y)),
+                    ("size", range(self.ranges.size)),
+                    ("orbit_speed", range(self.ranges.orbit_speed)),
+                    ("orbit_radius", range(self.ranges.orbit_radius)),
+                ]),
+            ),
+            (
+                "mouse",
+                Json::object(vec![
+                    ("radius", self.mouse_radius.into()),
+                    ("force", self.mouse_force.into()),
+                    ("max_attraction_force", self.max_attraction_force.into()),
+                ]),
+            ),
+            (
+                "boundary",
+                Json::object(vec![
+                    ("mode", boundary_mode_name(self.boundary_mode).into()),
+                    ("restitution", self.border_restitution.into()),
+                ]),
+            ),
+            (
+                "motion",
+                Json::object(vec![
+                    ("model", motion_model_name(self.motion_model).into()),
+                    ("linear_damping", self.linear_damping.into()),
+                    ("velocity_relaxation", self.velocity_relaxation.into()),
+                    ("max_speed", self.max_speed.into()),
+                ]),
+            ),
+            ("orbit_enabled", self.orbit_enabled.into()),
+            (
+                "timestep",
+                Json::object(vec![
+                    ("fixed_ms", self.fixed_timestep_ms.into()),
+                    (
+                        "max_catch_up_steps",
+                        f64::from(self.max_catch_up_steps).into(),
+                    ),
+                    ("loop_period_ms", self.loop_period_ms.into()),
+                ]),
+            ),
+            (
+                "color",
+                Json::object(vec![
+                    ("mode", color_mode_name(self.color.mode).into()),
+                    (
+                        "palette",
+                        Json::Array(self.color.palette.iter().map(|c| numbers(c)).collect()),
+                    ),
+                    (
+                        "gradient",
+                        Json::Array(
+                            self.color
+                                .gradient
+                                .iter()
+                                .map(|(position, c)| numbers(&[*position, c[0], c[1], c[2], c[3]]))
+                                .collect(),
+                        ),
+                    ),
+                    ("speed_range", range(self.color.speed_range)),
+                ]),
+            ),
+        ]);
+
+        Json::object(fields)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ParticleSystem {
+    pub fn from_config(config: &ParticleConfig) -> Result<ParticleSystem, ParticleError> {
+        config.validate()?;
+        let seed = config.seed.unwrap_or_else(rand::random);
+        Ok(ParticleSystem::build(config, seed))
+    }
+
+    /// The system's current settings, including its seed, as a config.
+    pub fn config(&self) -> ParticleConfig {
+        ParticleConfig {
+            width: self.width,
+            height: self.height,
+            particle_count: self.particles.len(),
+            connection_distance: self.connection_distance,
+            max_connections_per_particle: self.max_connections_per_particle,
+            max_connections: self.max_connections,
+            seed: Some(self.seed),
+            ranges: self.ranges,
+            mouse_radius: self.mouse_radius,
+            mouse_force: self.mouse_force,
+            max_attraction_force: self.max_attraction_force,
+            boundary_mode: self.boundary_mode,
+            border_restitution: self.border_restitution,
+            motion_model: self.motion_model,
+            linear_damping: self.linear_damping,
+            velocity_relaxation: self.velocity_relaxation,
+            max_speed: self.max_speed,
+            orbit_enabled: self.orbit_enabled,
+            fixed_timestep_ms: self.fixed_step_ms,
+            max_catch_up_steps: self.max_catch_up_steps,
+            loop_period_ms: self.loop_period_ms(),
+            color: self.colors.clone(),
+        }
+    }
+}
+
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+pub(crate) fn boundary_mode_name(mode: BoundaryMode) -> &'static str {
+    match mode {
+        BoundaryMode::Bounce => "bounce",
+        BoundaryMode::Wrap => "wrap",
+        BoundaryMode::Clamp => "clamp",
+        BoundaryMode::Respawn => "respawn",
+        BoundaryMode::Absorb => "absorb",
+    }
+}
+
+pub(crate) fn parse_boundary_mode(name: &str) -> Result<BoundaryMode, ParticleError> {
+    match name {
+        "bounce" => Ok(BoundaryMode::Bounce),
+        "wrap" => Ok(BoundaryMode::Wrap),
+        "clamp" => Ok(BoundaryMode::Clamp),
+        "respawn" => Ok(BoundaryMode::Respawn),
+        "absorb" => Ok(BoundaryMode::Absorb),
+        _ => Err(config_error(
+            "boundary.mode",
+            &format!("unknown mode `{name}`, expected bounce, wrap, clamp, respawn or absorb"),
+        )),
+    }
+}
+
+pub(crate) fn motion_model_name(model: MotionModel) -> &'static str {
+    match model {
+        MotionModel::Kinematic => "kinematic",
+        MotionModel::Inertial => "inertial",
+    }
+}
+
+pub(crate) fn parse_motion_model(name: &str) -> Result<MotionModel, ParticleError> {
+    match name {
+        "kinematic" => Ok(MotionModel::Kinematic),
+        "inertial" => Ok(MotionModel::Inertial),
+        _ => Err(config_error(
+            "motion.model",
+            &format!("unknown model `{name}`, expected kinematic or inertial"),
+        )),
+    }
+}
+
+pub(crate) fn color_mode_name(mode: ColorMode) -> &'static str {
+    match mode {
+        ColorMode::Palette => "palette",
+        ColorMode::Speed => "speed",
+        ColorMode::MouseAttraction => "mouse_attraction",
+    }
+}
+
+pub(crate) fn parse_color_mode(name: &str) -> Result<ColorMode, ParticleError> {
+    match name {
+        "palette" => Ok(ColorMode::Palette),
+        "speed" => Ok(ColorMode::Speed),
+        "mouse_attraction" => Ok(ColorMode::MouseAttraction),
+        _ => Err(config_error(
+            "color.mode",
+            &format!("unknown mode `{name}`, expected palette, speed or mouse_attraction"),
+        )),
+    }
+}
+
+fn color(value: &Json, mut color: ColorRules) -> Result<ColorRules, ParticleError> {
+    for (key, value) in object(value, "color")? {
+        match key.as_str() {
+            "mode" => color.mode = parse_color_mode(string(value, "color.mode")?)?,
+            "palette" => {
+                color.palette = array(value, "color.palette")?
+                    .iter()
+                    .map(|entry| {
+                        let [r, g, b, a] = fixed(entry, "color.palette", "an [r, g, b, a] array")?;
+                        Ok([r, g, b, a])
+                    })
+                    .collect::<Result<_, ParticleError>>()?
+            }
+            "gradient" => {
+                color.gradient = array(value, "color.gradient")?
+                    .iter()
+                    .map(|entry| {
+                        let [position, r, g, b, a] =
+                            fixed(entry, "color.gradient", "a [position, r, g, b, a] array")?;
+                        Ok((position, [r, g, b, a]))
+                    })
+                    .collect::<Result<_, ParticleError>>()?
+            }
+            "speed_range" => {
+                let [min, max] = fixed(value, "color.speed_range", "a [min, max] array")?;
+                color.speed_range = Range::new(min, max);
+            }
+            _ => return Err(unknown_field("color", key)),
+        }
+    }
+    Ok(color)
+}
+
+fn fixed<const N: usize>(
+    value: &Json,
+    path: &str,
+    expected: &str,
+) -> Result<[f64; N], ParticleError> {
+    let items = value
+        .as_array()
+        .filter(|items| items.len() == N)
+        .ok_or_else(|| config_error(path, &format!("expected {expected}")))?;
+    let mut numbers = [0.0; N];
+    for (slot, item) in numbers.iter_mut().zip(items) {
+        *slot = number(item, path)?;
+    }
+    Ok(numbers)
+}
+
+fn numbers(values: &[f64]) -> Json {
+    Json::Array(values.iter().map(|&value| value.into()).collect())
+}
+
+fn ranges(value: &Json, mut ranges: ParticleRanges) -> Result<ParticleRanges, ParticleError> {
+    for (key, value) in object(value, "ranges")? {
+        let slot = match key.as_str() {
+            "velocity_x" => &mut ranges.velocity_x,
+            "velocity_y" => &mut ranges.velocity_y,
+            "size" => &mut ranges.size,
+            "orbit_speed" => &mut ranges.orbit_speed,
+            "orbit_radius" => &mut ranges.orbit_radius,
+            _ => return Err(unknown_field("ranges", key)),
+        };
+        let path = format!("ranges.{key}");
+        *slot = match value.as_array() {
+            Some([min, max]) => Range::new(number(min, &path)?, number(max, &path)?),
+            _ => return Err(config_error(&path, "expected a [min, max] array")),
+        };
+    }
+    Ok(ranges)
+}
+
+fn seed(value: &Json) -> Result<Option<u64>, ParticleError> {
+    match value {
+        Json::Null => Ok(None),
+        Json::Number(number)
+            if number.fract() == 0.0 && *number >= 0.0 && *number <= MAX_SAFE_INTEGER as f64 =>
+        {
+            Ok(Some(*number as u64))
+        }
+        Json::String(text) => Ok(Some(string_seed(text))),
+        _ => Err(config_error(
+            "seed",
+            "expected a non-negative integer below 2^53, a string or null",
+        )),
+    }
+}
+
+fn array<'a>(value: &'a Json, path: &str) -> Result<&'a [Json], ParticleError> {
+    value
+        .as_array()
+        .ok_or_else(|| type_error(path, "an array", value))
+}
+
+fn object<'a>(value: &'a Json, path: &str) -> Result<&'a [(String, Json)], ParticleError> {
+    value
+        .as_object()
+        .ok_or_else(|| type_error(path, "an object", value))
+}
+
+fn number(value: &Json, path: &str) -> Result<f64, ParticleError> {
+    value
+        .as_f64()
+        .ok_or_else(|| type_error(path, "a number", value))
+}
+
+fn count(value: &Json, path: &str) -> Result<usize, ParticleError> {
+    let number = number(value, path)?;
+    if number.fract() == 0.0 && number >= 0.0 && number <= MAX_SAFE_INTEGER as f64 {
+        Ok(number as usize)
+    } else {
+        Err(config_error(path, "expected a non-negative integer"))
+    }
+}
+
+fn boolean(value: &Json, path: &str) -> Result<bool, ParticleError> {
+    value
+        .as_bool()
+        .ok_or_else(|| type_error(path, "a boolean", value))
+}
+
+fn string<'a>(value: &'a Json, path: &str) -> Result<&'a str, ParticleError> {
+    value
+        .as_str()
+        .ok_or_else(|| type_error(path, "a string", value))
+}
+
+fn type_error(path: &str, expected: &str, value: &Json) -> ParticleError {
+    config_error(
+        path,
+        &format!("expected {expected}, got {}", value.type_name()),
+    )
+}
+
+fn unknown_field(parent: &str, key: &str) -> ParticleError {
+    let path = if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{parent}.{key}")
+    };
+    config_error(&path, "unknown field")
+}
+
+pub(crate) fn config_error(path: &str, message: &str) -> ParticleError {
+    ParticleError::Config {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{BoundaryMode, ParticleSystem};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// A pair of particles within `connection_distance` of each other.
+///
+/// `dx`/`dy` point from `a` to the nearest image of `b`, which differs from
+/// `b`'s actual position only when the line crosses an edge in wrap mode.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Link {
+    pub a: usize,
+    pub b: usize,
+    pub dx: f64,
+    pub dy: f64,
+    pub crosses_edge: bool,
+    pub opacity: f64,
+}
+
+/// Working memory for `collect_links`, kept by its callers so that building
+/// links every frame does not allocate once the buffers have grown.
+#[derive(Default)]
+pub(crate) struct LinkScratch {
+    candidates: Vec<usize>,
+    order: Vec<usize>,
+    degree: Vec<usize>,
+    keep: Vec<bool>,
+}
+
+/// How `prepare_frame` writes connection lines.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionFormat {
+    /// `x1, y1, x2, y2, opacity` records in the connection buffer.
+    Segments = 0,
+    /// Pairs of particle indices into the particle buffer, with a parallel
+    /// opacity buffer. Lines crossing an edge in wrap mode cannot be drawn
+    /// between two particle positions, so `prepare_frame` writes those as
+    /// segment records to the connection buffer instead.
+    Indexed = 1,
+}
+
+/// How many connection lines the last frame considered and why some of them
+/// were left out. Counts are per particle pair; a pair drawn across an edge
+/// in wrap mode still counts once.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConnectionStats {
+    /// Pairs within `connection_distance`.
+    pub candidates: usize,
+    /// Pairs dropped because an endpoint already had its nearest
+    /// `max_connections_per_particle` lines.
+    pub dropped_by_degree: usize,
+    /// Pairs dropped by the `max_connections` budget.
+    pub dropped_by_budget: usize,
+    /// Pairs left after both limits.
+    pub drawn: usize,
+}
+
+impl Link {
+    /// Calls `segment` with the line's endpoints given positions for `a` and
+    /// `b`; lines crossing an edge are split into one segment per side.
+    pub fn segments(&self, a: (f64, f64), b: (f64, f64), mut segment: impl FnMut([f64; 4])) {
+        if self.crosses_edge {
+            // 跨越边缘的连接线拆成两段，分别从两端画出屏幕
+            segment([a.0, a.1, a.0 + self.dx, a.1 + self.dy]);
+            segment([b.0 - self.dx, b.1 - self.dy, b.0, b.1]);
+        } else {
+            segment([a.0, a.1, b.0, b.1]);
+        }
+    }
+}
+
+impl ParticleSystem {
+    /// Connection lines as `x1, y1, x2, y2, opacity` records, followed by
+    /// `r, g, b, a` when `ParticleField::Color` is enabled, in the same order
+    /// `calculate_connections` returns them.
+    pub fn connection_data(&self) -> Vec<f64> {
+        let mut links = Vec::new();
+        self.collect_links(&mut LinkScratch::default(), &mut links);
+
+        let mut connections = Vec::with_capacity(links.len() * self.connection_stride());
+        for link in &links {
+            let p1 = self.particles[link.a];
+            let p2 = self.particles[link.b];
+            let color = self.link_color(link);
+            link.segments((p1.x, p1.y), (p2.x, p2.y), |segment| {
+                connections.extend_from_slice(&segment);
+                connections.push(link.opacity);
+                if let Some(color) = color {
+                    connections.extend_from_slice(&color);
+                }
+            });
+        }
+
+        connections
+    }
+
+    /// Connection lines as particle index pairs and their opacities, in the
+    /// same order as `connection_data`. Lines crossing an edge in wrap mode
+    /// are not included; see `ConnectionFormat::Indexed`.
+    pub fn connection_indices(&self) -> (Vec<u32>, Vec<f32>) {
+        let mut links = Vec::new();
+        self.collect_links(&mut LinkScratch::default(), &mut links);
+
+        let mut indices = Vec::with_capacity(links.len() * 2);
+        let mut opacities = Vec::with_capacity(links.len());
+        write_indexed(&links, &mut indices, &mut opacities);
+        (indices, opacities)
+    }
+
+    pub(crate) fn collect_links(&self, scratch: &mut LinkScratch, links: &mut Vec<Link>) {
+        links.clear();
+        if self.connection_distance.is_nan() || self.connection_distance <= 0.0 {
+            self.connection_stats.set(ConnectionStats::default());
+            return;
+        }
+
+        // 循环模式在环面上运动，连接线同样跨越边缘
+        let wrap = self.boundary_mode == BoundaryMode::Wrap || self.looping.is_some();
+
+        for i in 0..self.particles.len() {
+            let p1 = self.particles[i];
+
+            // 只检查相邻单元格中的粒子，按索引排序以保持与逐对遍历相同的输出顺序
+            let (col, row) = self.grid.cell_of(p1.x, p1.y);
+            let candidates = &mut scratch.candidates;
+            candidates.clear();
+            self.grid.neighbors(col, row, wrap, candidates);
+            candidates.retain(|&j| j > i);
+            candidates.sort_unstable();
+
+            for &j in candidates.iter() {
+                let p2 = self.particles[j];
+
+                let mut dx = p2.x - p1.x;
+                let mut dy = p2.y - p1.y;
+                let mut crosses_edge = false;
+                if wrap {
+                    // 环绕模式下取最近的镜像
+                    let wrapped_dx = wrapped_delta(dx, self.width);
+                    let wrapped_dy = wrapped_delta(dy, self.height);
+                    crosses_edge = wrapped_dx != dx || wrapped_dy != dy;
+                    dx = wrapped_dx;
+                    dy = wrapped_dy;
+                } else if dx.abs() > self.width / 2.0 || dy.abs() > self.height / 2.0 {
+                    // 跳过屏幕两端的粒子
+                    continue;
+                }
+
+                let distance = (dx * dx + dy * dy).sqrt();
+
+                if distance < self.connection_distance {
+                    let opacity = 1.0 - (distance / self.connection_distance);
+
+                    let d1 = ((p1.x - self.mouse_x).powi(2) + (p1.y - self.mouse_y).powi(2)).sqrt();
+                    let d2 = ((p2.x - self.mouse_x).powi(2) + (p2.y - self.mouse_y).powi(2)).sqrt();
+
+                    let mut final_opacity = opacity;
+                    if d1 < self.mouse_radius || d2 < self.mouse_radius {
+                        final_opacity *= 1.3; // 稍微增强鼠标附近的连接线
+                    }
+                    // 随淡入淡出较弱的一端一起变淡
+                    final_opacity *= p1.fade().min(p2.fade());
+
+                    links.push(Link {
+                        a: i,
+                        b: j,
+                        dx,
+                        dy,
+                        crosses_edge,
+                        opacity: final_opacity,
+                    });
+                }
+            }
+        }
+
+        self.limit_links(scratch, links);
+    }
+
+    /// Applies the per-particle and per-frame limits, keeping the surviving
+    /// links in their original order.
+    fn limit_links(&self, scratch: &mut LinkScratch, links: &mut Vec<Link>) {
+        let mut stats = ConnectionStats {
+            candidates: links.len(),
+            ..ConnectionStats::default()
+        };
+        let LinkScratch {
+            order,
+            degree,
+            keep,
+            ..
+        } = scratch;
+
+        if self.max_connections_per_particle > 0 {
+            // 按距离从近到远贪心分配，每个粒子只保留最近的 k 条
+            order.clear();
+            order.extend(0..links.len());
+            order.sort_by(|&i, &j| length_sq(&links[i]).total_cmp(&length_sq(&links[j])));
+
+            degree.clear();
+            degree.resize(self.particles.len(), 0);
+            keep.clear();
+            keep.resize(links.len(), false);
+            for &index in order.iter() {
+                let link = &links[index];
+                if degree[link.a] < self.max_connections_per_particle
+                    && degree[link.b] < self.max_connections_per_particle
+                {
+                    degree[link.a] += 1;
+                    degree[link.b] += 1;
+                    keep[index] = true;
+                }
+            }
+
+            retain_kept(links, keep);
+            stats.dropped_by_degree = stats.candidates - links.len();
+        }
+
+        if self.max_connections > 0 && links.len() > self.max_connections {
+            // 预算不足时优先保留最显眼的连接线
+            order.clear();
+            order.extend(0..links.len());
+            order.sort_by(|&i, &j| links[j].opacity.total_cmp(&links[i].opacity));
+
+            keep.clear();
+            keep.resize(links.len(), false);
+            for &index in &order[..self.max_connections] {
+                keep[index] = true;
+            }
+
+            let before = links.len();
+            retain_kept(links, keep);
+            stats.dropped_by_budget = before - links.len();
+        }
+
+        stats.drawn = links.len();
+        self.connection_stats.set(stats);
+    }
+}
+
+pub(crate) fn write_indexed(links: &[Link], indices: &mut Vec<u32>, opacities: &mut Vec<f32>) {
+    for link in links.iter().filter(|link| !link.crosses_edge) {
+        indices.extend_from_slice(&[link.a as u32, link.b as u32]);
+        opacities.push(link.opacity as f32);
+    }
+}
+
+fn retain_kept(links: &mut Vec<Link>, keep: &[bool]) {
+    let mut keep = keep.iter();
+    links.retain(|_| keep.next().copied().unwrap_or(false));
+}
+
+fn length_sq(link: &Link) -> f64 {
+    link.dx * link.dx + link.dy * link.dy
+}
+
+fn wrapped_delta(delta: f64, extent: f64) -> f64 {
+    if extent > 0.0 && delta.abs() > extent / 2.0 {
+        delta - extent * delta.signum()
+    } else {
+        delta
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ParticleSystem {
+    /// Statistics from the most recent `calculate_connections`,
+    /// `connection_data` or `prepare_frame`.
+    pub fn connection_stats(&self) -> ConnectionStats {
+        self.connection_stats.get()
+    }
+
+    pub fn connection_format(&self) -> ConnectionFormat {
+        self.connection_format
+    }
+
+    pub fn set_connection_format(&mut self, format: ConnectionFormat) {
+        self.connection_format = format;
+    }
+}
@@ -0,0 +1,334 @@
+use crate::rng::Xoshiro256;
+use crate::{params, particle_at, ParticleError, ParticleSystem, Range};
+use rand::Rng;
+use std::f64::consts::PI;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Where an emitter places the particles it spawns.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmitterShape {
+    /// At `(x1, y1)`.
+    Point = 0,
+    /// Anywhere on the segment from `(x1, y1)` to `(x2, y2)`.
+    Line = 1,
+    /// Anywhere inside the rectangle with corners `(x1, y1)` and `(x2, y2)`.
+    Rectangle = 2,
+    /// Along the path the mouse travelled during the step; nothing is
+    /// spawned while the mouse is still or outside the canvas.
+    MouseTrail = 3,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Emitter {
+    pub id: u32,
+    pub shape: EmitterShape,
+    pub x1: f64,
+    pub y1: f64,
+    pub x2: f64,
+    pub y2: f64,
+    pub rate: f64,
+    pub burst_count: u32,
+    pub pending_bursts: u32,
+    pub direction: f64,
+    pub spread: f64,
+    pub speed: Range,
+    pub lifetime_ms: f64,
+    pub fade_in_ms: f64,
+    pub fade_out_ms: f64,
+    pub enabled: bool,
+    pub accumulator: f64,
+    pub last_mouse: Option<(f64, f64)>,
+}
+
+impl Emitter {
+    /// Checks the fields against the rules the setters enforce.
+    pub fn validate(&self) -> Result<(), ParticleError> {
+        corners(self.x1, self.y1, self.x2, self.y2)?;
+        params::non_negative("rate", self.rate)?;
+        params::finite("direction", self.direction)?;
+        if !(0.0..=PI).contains(&self.spread) {
+            return Err(ParticleError::InvalidValue {
+                name: "spread",
+                value: self.spread,
+                expected: "between 0 and π",
+            });
+        }
+        emitter_speed(self.speed.min, self.speed.max)?;
+        duration("lifetime", self.lifetime_ms)?;
+        duration("fade_in", self.fade_in_ms)?;
+        duration("fade_out", self.fade_out_ms)?;
+        params::fraction("accumulator", self.accumulator)?;
+        Ok(())
+    }
+
+    /// Number of particles due this step: the continuous rate plus any
+    /// pending bursts.
+    fn due(&mut self, dt_ms: f64, moving: bool) -> u32 {
+        let mut count = self.pending_bursts.saturating_mul(self.burst_count);
+        self.pending_bursts = 0;
+
+        // 鼠标轨迹静止时不累计，避免移动瞬间一次性喷出
+        if self.enabled && (self.shape != EmitterShape::MouseTrail || moving) {
+            self.accumulator += self.rate * dt_ms / 1000.0;
+            let whole = self.accumulator.floor();
+            self.accumulator -= whole;
+            count = count.saturating_add(whole as u32);
+        }
+        count
+    }
+
+    fn position(&self, rng: &mut Xoshiro256, trail: ((f64, f64), (f64, f64))) -> (f64, f64) {
+        match self.shape {
+            EmitterShape::Point => (self.x1, self.y1),
+            EmitterShape::Line => {
+                let t = rng.gen::<f64>();
+                (
+                    self.x1 + (self.x2 - self.x1) * t,
+                    self.y1 + (self.y2 - self.y1) * t,
+                )
+            }
+            EmitterShape::Rectangle => (
+                self.x1 + (self.x2 - self.x1) * rng.gen::<f64>(),
+                self.y1 + (self.y2 - self.y1) * rng.gen::<f64>(),
+            ),
+            EmitterShape::MouseTrail => {
+                let ((x1, y1), (x2, y2)) = trail;
+                let t = rng.gen::<f64>();
+                (x1 + (x2 - x1) * t, y1 + (y2 - y1) * t)
+            }
+        }
+    }
+
+    fn velocity(&self, rng: &mut Xoshiro256) -> (f64, f64) {
+        let angle = if self.spread > 0.0 {
+            self.direction + rng.gen_range(-self.spread..self.spread)
+        } else {
+            self.direction
+        };
+        let speed = self.speed.sample(rng);
+        (angle.cos() * speed, angle.sin() * speed)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ParticleSystem {
+    /// Adds an emitter and returns its id. The coordinates are interpreted
+    /// according to `shape`; a mouse-trail emitter ignores them.
+    ///
+    /// New emitters spawn 10 particles per second in every direction at
+    /// 0.2–1 pixels per step, each living for 3 seconds.
+    pub fn add_emitter(
+        &mut self,
+        shape: EmitterShape,
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+    ) -> Result<u32, ParticleError> {
+        let (x1, y1, x2, y2) = corners(x1, y1, x2, y2)?;
+        let id = self.next_emitter_id;
+        self.next_emitter_id = self.next_emitter_id.wrapping_add(1).max(1);
+        self.emitters.push(Emitter {
+            id,
+            shape,
+            x1,
+            y1,
+            x2,
+            y2,
+            rate: 10.0,
+            burst_count: 0,
+            pending_bursts: 1,
+            direction: 0.0,
+            spread: PI,
+            speed: Range::new(0.2, 1.0),
+            lifetime_ms: 3000.0,
+            fade_in_ms: 300.0,
+            fade_out_ms: 600.0,
+            enabled: true,
+            accumulator: 0.0,
+            last_mouse: None,
+        });
+        Ok(id)
+    }
+
+    /// Returns `Ok(false)` when no emitter has `id`.
+    pub fn move_emitter(
+        &mut self,
+        id: u32,
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+    ) -> Result<bool, ParticleError> {
+        let (x1, y1, x2, y2) = corners(x1, y1, x2, y2)?;
+        Ok(self.with_emitter(id, |emitter| {
+            emitter.x1 = x1;
+            emitter.y1 = y1;
+            emitter.x2 = x2;
+            emitter.y2 = y2;
+        }))
+    }
+
+    /// Continuous spawn rate in particles per second of simulated time.
+    pub fn set_emitter_rate(&mut self, id: u32, per_second: f64) -> Result<bool, ParticleError> {
+        let rate = params::non_negative("rate", per_second)?;
+        Ok(self.with_emitter(id, |emitter| emitter.rate = rate))
+    }
+
+    /// Number of particles spawned at once on the emitter's first step and
+    /// whenever `trigger_emitter` is called.
+    pub fn set_emitter_burst(&mut self, id: u32, count: u32) -> bool {
+        self.with_emitter(id, |emitter| emitter.burst_count = count)
+    }
+
+    /// Queues one burst for the next step.
+    pub fn trigger_emitter(&mut self, id: u32) -> bool {
+        self.with_emitter(id, |emitter| emitter.pending_bursts += 1)
+    }
+
+    /// Launches particles within `spread` radians either side of
+    /// `direction`, at a speed between `min_speed` and `max_speed` pixels per
+    /// step. `spread` is clamped to `[0, π]`.
+    pub fn set_emitter_velocity(
+        &mut self,
+        id: u32,
+        direction: f64,
+        spread: f64,
+        min_speed: f64,
+        max_speed: f64,
+    ) -> Result<bool, ParticleError> {
+        let direction = params::finite("direction", direction)?;
+        let spread = params::finite("spread", spread)?.clamp(0.0, PI);
+        let speed = emitter_speed(min_speed, max_speed)?;
+        Ok(self.with_emitter(id, |emitter| {
+            emitter.direction = direction;
+            emitter.spread = spread;
+            emitter.speed = speed;
+        }))
+    }
+
+    /// How long spawned particles live and how long they take to fade in
+    /// after birth and out before death. An infinite lifetime makes them
+    /// permanent.
+    pub fn set_emitter_lifetime(
+        &mut self,
+        id: u32,
+        lifetime_ms: f64,
+        fade_in_ms: f64,
+        fade_out_ms: f64,
+    ) -> Result<bool, ParticleError> {
+        let lifetime_ms = duration("lifetime", lifetime_ms)?;
+        let fade_in_ms = duration("fade_in", fade_in_ms)?;
+        let fade_out_ms = duration("fade_out", fade_out_ms)?;
+        Ok(self.with_emitter(id, |emitter| {
+            emitter.lifetime_ms = lifetime_ms;
+            emitter.fade_in_ms = fade_in_ms;
+            emitter.fade_out_ms = fade_out_ms;
+        }))
+    }
+
+    /// Pauses or resumes continuous emission; bursts still fire.
+    pub fn set_emitter_enabled(&mut self, id: u32, enabled: bool) -> bool {
+        self.with_emitter(id, |emitter| emitter.enabled = enabled)
+    }
+
+    /// Removes the emitter; particles it already spawned live out their
+    /// lifetime.
+    pub fn remove_emitter(&mut self, id: u32) -> bool {
+        let len = self.emitters.len();
+        self.emitters.retain(|emitter| emitter.id != id);
+        self.emitters.len() != len
+    }
+
+    pub fn clear_emitters(&mut self) {
+        self.emitters.clear();
+    }
+
+    pub fn emitter_count(&self) -> usize {
+        self.emitters.len()
+    }
+
+    pub fn emitter_ids(&self) -> Vec<u32> {
+        self.emitters.iter().map(|emitter| emitter.id).collect()
+    }
+}
+
+fn corners(x1: f64, y1: f64, x2: f64, y2: f64) -> Result<(f64, f64, f64, f64), ParticleError> {
+    Ok((
+        params::finite("x1", x1)?,
+        params::finite("y1", y1)?,
+        params::finite("x2", x2)?,
+        params::finite("y2", y2)?,
+    ))
+}
+
+/// Durations may be infinite, for particles that never expire or fade.
+fn duration(name: &'static str, value: f64) -> Result<f64, ParticleError> {
+    if value >= 0.0 {
+        Ok(value)
+    } else {
+        Err(ParticleError::InvalidValue {
+            name,
+            value,
+            expected: "a non-negative number",
+        })
+    }
+}
+
+fn emitter_speed(min: f64, max: f64) -> Result<Range, ParticleError> {
+    let speed = Range::new(min, max).validate("speed")?;
+    params::non_negative("speed min", speed.min)?;
+    Ok(speed)
+}
+
+impl ParticleSystem {
+    fn with_emitter(&mut self, id: u32, f: impl FnOnce(&mut Emitter)) -> bool {
+        match self.emitters.iter_mut().find(|emitter| emitter.id == id) {
+            Some(emitter) => {
+                f(emitter);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn run_emitters(&mut self, dt_ms: f64, mouse_active: bool) {
+        let mouse = (self.mouse_x, self.mouse_y);
+        let mut emitters = std::mem::take(&mut self.emitters);
+
+        'emitters: for emitter in &mut emitters {
+            let mut trail = (mouse, mouse);
+            let mut moving = false;
+            if emitter.shape == EmitterShape::MouseTrail {
+                if !mouse_active {
+                    // 鼠标离开画布时清空轨迹，重新进入时不会从旧位置连出一条线
+                    emitter.last_mouse = None;
+                    continue;
+                }
+                let from = emitter.last_mouse.unwrap_or(mouse);
+                trail = (from, mouse);
+                moving = from != mouse;
+                emitter.last_mouse = Some(mouse);
+            }
+
+            for _ in 0..emitter.due(dt_ms, moving) {
+                let (x, y) = emitter.position(&mut self.rng, trail);
+                let (vx, vy) = emitter.velocity(&mut self.rng);
+                let mut particle = particle_at(&mut self.rng, x, y, &self.ranges);
+                particle.set_drift(vx, vy);
+                particle.lifetime_ms = emitter.lifetime_ms;
+                particle.fade_in_ms = emitter.fade_in_ms;
+                particle.fade_out_ms = emitter.fade_out_ms;
+
+                if self.push_particle(particle).is_err() {
+                    // 已达到粒子上限，丢弃本步剩余的生成
+                    break 'emitters;
+                }
+            }
+        }
+
+        self.emitters = emitters;
+    }
+}
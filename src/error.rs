@@ -0,0 +1,73 @@
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParticleError {
+    /// A parameter was given a value outside the accepted domain.
+    InvalidValue {
+        name: &'static str,
+        value: f64,
+        expected: &'static str,
+    },
+    /// A `[min, max]` range was empty or not finite.
+    InvalidRange {
+        name: &'static str,
+        min: f64,
+        max: f64,
+    },
+    /// A config document was not valid JSON.
+    Json {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// A config field had the wrong shape or an unknown name.
+    Config {
+        path: String,
+        message: String,
+    },
+    UnknownPreset(String),
+    /// Adding particles would exceed the number of ids available.
+    TooManyParticles,
+    /// A snapshot was corrupt, truncated or written by an incompatible
+    /// version.
+    Snapshot(String),
+    /// A recording of calls was corrupt, truncated or written by an
+    /// incompatible version.
+    Recording(String),
+}
+
+impl fmt::Display for ParticleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParticleError::InvalidValue {
+                name,
+                value,
+                expected,
+            } => write!(f, "{name} must be {expected}, got {value}"),
+            ParticleError::InvalidRange { name, min, max } => write!(
+                f,
+                "{name} must be a finite range with min <= max, got [{min}, {max}]"
+            ),
+            ParticleError::Json {
+                line,
+                column,
+                message,
+            } => write!(f, "invalid JSON at line {line}, column {column}: {message}"),
+            ParticleError::Config { path, message } => write!(f, "{path}: {message}"),
+            ParticleError::UnknownPreset(name) => write!(
+                f,
+                "unknown preset `{name}`, expected one of: {}",
+                crate::config::PRESET_NAMES.join(", ")
+            ),
+            ParticleError::TooManyParticles => write!(
+                f,
+                "a system can hold at most {} particles",
+                crate::ids::MAX_PARTICLES
+            ),
+            ParticleError::Snapshot(message) => write!(f, "invalid snapshot: {message}"),
+            ParticleError::Recording(message) => write!(f, "invalid recording: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ParticleError {}
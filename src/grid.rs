@@ -0,0 +1,156 @@
+use crate::Particle;
+
+// 网格过密时放大单元格，避免连接距离很小时分配过多内存
+const MIN_CELLS: usize = 1024;
+const CELLS_PER_PARTICLE: usize = 4;
+
+/// Uniform grid bucketing particle indices by position so that neighbour
+/// queries only visit the surrounding 3x3 block of cells.
+pub(crate) struct SpatialGrid {
+    cell_width: f64,
+    cell_height: f64,
+    cols: usize,
+    rows: usize,
+    cell_start: Vec<usize>,
+    entries: Vec<usize>,
+    particle_cells: Vec<usize>,
+}
+
+impl SpatialGrid {
+    pub fn new() -> SpatialGrid {
+        SpatialGrid {
+            cell_width: 1.0,
+            cell_height: 1.0,
+            cols: 1,
+            rows: 1,
+            cell_start: vec![0, 0],
+            entries: Vec::new(),
+            particle_cells: Vec::new(),
+        }
+    }
+
+    pub fn rebuild(&mut self, particles: &[Particle], width: f64, height: f64, min_cell_size: f64) {
+        let width = if width.is_finite() {
+            width.max(1.0)
+        } else {
+            1.0
+        };
+        let height = if height.is_finite() {
+            height.max(1.0)
+        } else {
+            1.0
+        };
+
+        let mut cell_size = if min_cell_size.is_finite() && min_cell_size > 0.0 {
+            min_cell_size
+        } else {
+            width.max(height)
+        };
+        let max_cells = (particles.len() * CELLS_PER_PARTICLE).max(MIN_CELLS) as f64;
+        if (width / cell_size).floor() * (height / cell_size).floor() > max_cells {
+            cell_size = cell_size.max((width * height / max_cells).sqrt());
+        }
+
+        // 单元格尺寸取整除画布，保证环绕时边缘两侧的单元格同样不小于连接距离
+        self.cols = ((width / cell_size).floor() as usize).max(1);
+        self.rows = ((height / cell_size).floor() as usize).max(1);
+        self.cell_width = width / self.cols as f64;
+        self.cell_height = height / self.rows as f64;
+
+        let cell_count = self.cols * self.rows;
+        self.cell_start.clear();
+        self.cell_start.resize(cell_count + 1, 0);
+        self.particle_cells.clear();
+
+        for particle in particles {
+            let (col, row) = self.cell_of(particle.x, particle.y);
+            let cell = row * self.cols + col;
+            self.particle_cells.push(cell);
+            self.cell_start[cell + 1] += 1;
+        }
+        for cell in 0..cell_count {
+            self.cell_start[cell + 1] += self.cell_start[cell];
+        }
+
+        // 计数排序：同一单元格内的索引保持升序
+        self.entries.clear();
+        self.entries.resize(particles.len(), 0);
+        let mut cursor = self.cell_start[..cell_count].to_vec();
+        for (idx, &cell) in self.particle_cells.iter().enumerate() {
+            self.entries[cursor[cell]] = idx;
+            cursor[cell] += 1;
+        }
+    }
+
+    pub fn cell_of(&self, x: f64, y: f64) -> (usize, usize) {
+        (
+            axis_cell(x, self.cell_width, self.cols),
+            axis_cell(y, self.cell_height, self.rows),
+        )
+    }
+
+    pub fn cell(&self, col: usize, row: usize) -> &[usize] {
+        let cell = row * self.cols + col;
+        &self.entries[self.cell_start[cell]..self.cell_start[cell + 1]]
+    }
+
+    /// Appends every index stored in the 3x3 block around `(col, row)`,
+    /// wrapping around the edges of the grid when `wrap` is set.
+    pub fn neighbors(&self, col: usize, row: usize, wrap: bool, out: &mut Vec<usize>) {
+        let cols = axis_neighbors(col, self.cols, wrap);
+        for &r in axis_neighbors(row, self.rows, wrap).as_slice() {
+            for &c in cols.as_slice() {
+                out.extend_from_slice(self.cell(c, r));
+            }
+        }
+    }
+}
+
+struct AxisNeighbors {
+    cells: [usize; 3],
+    len: usize,
+}
+
+impl AxisNeighbors {
+    fn as_slice(&self) -> &[usize] {
+        &self.cells[..self.len]
+    }
+}
+
+fn axis_neighbors(cell: usize, count: usize, wrap: bool) -> AxisNeighbors {
+    let mut neighbors = AxisNeighbors {
+        cells: [0; 3],
+        len: 0,
+    };
+    let candidates = if wrap {
+        [
+            Some((cell + count - 1) % count),
+            Some(cell),
+            Some((cell + 1) % count),
+        ]
+    } else {
+        [
+            cell.checked_sub(1),
+            Some(cell),
+            Some(cell + 1).filter(|&c| c < count),
+        ]
+    };
+
+    // 列数少于 3 时环绕会得到重复的单元格
+    for candidate in candidates.into_iter().flatten() {
+        if !neighbors.as_slice().contains(&candidate) {
+            neighbors.cells[neighbors.len] = candidate;
+            neighbors.len += 1;
+        }
+    }
+    neighbors
+}
+
+fn axis_cell(value: f64, cell_size: f64, count: usize) -> usize {
+    let cell = (value / cell_size).floor();
+    if cell.is_nan() || cell < 0.0 {
+        0
+    } else {
+        (cell as usize).min(count - 1)
+    }
+}
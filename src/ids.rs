@@ -0,0 +1,285 @@
+use crate::{params, particle_at, random_particle, Particle, ParticleError, ParticleSystem};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// 低 20 位为槽位，高 12 位为代数；槽位复用时代数加一，旧 ID 随之失效。
+// 代数用尽的槽位永久停用，否则回绕后旧 ID 会重新生效
+const INDEX_BITS: u32 = 20;
+const INDEX_MASK: u32 = (1 << INDEX_BITS) - 1;
+const GENERATION_MASK: u32 = u32::MAX >> INDEX_BITS;
+pub(crate) const MAX_PARTICLES: usize = 1 << INDEX_BITS;
+
+const VACANT: u32 = u32::MAX;
+
+#[derive(Clone, Copy)]
+struct Slot {
+    generation: u32,
+    index: u32,
+}
+
+/// Generational handles mapping stable particle ids to their current
+/// position in the dense particle array.
+#[derive(Default)]
+pub(crate) struct ParticleIds {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+}
+
+impl ParticleIds {
+    pub fn allocate(&mut self, index: usize) -> Result<u32, ParticleError> {
+        let slot = match self.free.pop() {
+            Some(slot) => slot,
+            None if self.slots.len() < MAX_PARTICLES => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    index: VACANT,
+                });
+                (self.slots.len() - 1) as u32
+            }
+            None => return Err(ParticleError::TooManyParticles),
+        };
+
+        let entry = &mut self.slots[slot as usize];
+        entry.index = index as u32;
+        Ok(entry.generation << INDEX_BITS | slot)
+    }
+
+    pub fn lookup(&self, id: u32) -> Option<usize> {
+        let slot = self.slots.get((id & INDEX_MASK) as usize)?;
+        (slot.index != VACANT && slot.generation == id >> INDEX_BITS).then_some(slot.index as usize)
+    }
+
+    /// Invalidates `id`. Its slot is reused unless it has run out of
+    /// generations, in which case it is retired for good.
+    pub fn release(&mut self, id: u32) {
+        if self.lookup(id).is_some() {
+            let slot_index = id & INDEX_MASK;
+            let slot = &mut self.slots[slot_index as usize];
+            slot.index = VACANT;
+            if slot.generation < GENERATION_MASK {
+                slot.generation += 1;
+                self.free.push(slot_index);
+            }
+        }
+    }
+
+    /// Invalidates `id` and returns a fresh id for the same index, normally
+    /// in the same slot.
+    pub fn renew(&mut self, id: u32) -> Result<u32, ParticleError> {
+        match self.lookup(id) {
+            Some(index) => {
+                self.release(id);
+                self.allocate(index)
+            }
+            None => Ok(id),
+        }
+    }
+
+    /// Records that the particle with `id` now lives at `index`.
+    pub fn relocate(&mut self, id: u32, index: usize) {
+        if self.lookup(id).is_some() {
+            self.slots[(id & INDEX_MASK) as usize].index = index as u32;
+        }
+    }
+
+    /// Generation and particle index of every slot, and the free list in
+    /// the order slots are reused.
+    pub fn parts(&self) -> (Vec<(u32, Option<u32>)>, &[u32]) {
+        let slots = self
+            .slots
+            .iter()
+            .map(|slot| {
+                (
+                    slot.generation,
+                    (slot.index != VACANT).then_some(slot.index),
+                )
+            })
+            .collect();
+        (slots, &self.free)
+    }
+
+    /// Inverse of `parts`. Returns `None` unless every particle's id maps to
+    /// its own index and every vacant slot is either retired or on the free
+    /// list exactly once.
+    pub fn from_parts(
+        slots: &[(u32, Option<u32>)],
+        free: Vec<u32>,
+        particles: &[Particle],
+    ) -> Option<ParticleIds> {
+        if slots.len() > MAX_PARTICLES {
+            return None;
+        }
+        let mut ids = ParticleIds {
+            slots: Vec::with_capacity(slots.len()),
+            free,
+        };
+        let mut occupied = 0;
+        for &(generation, index) in slots {
+            if generation > GENERATION_MASK {
+                return None;
+            }
+            if let Some(index) = index {
+                if index as usize >= particles.len() {
+                    return None;
+                }
+                occupied += 1;
+            }
+            ids.slots.push(Slot {
+                generation,
+                index: index.unwrap_or(VACANT),
+            });
+        }
+
+        let mut listed = vec![false; slots.len()];
+        for &slot in &ids.free {
+            match ids.slots.get(slot as usize) {
+                Some(entry)
+                    if entry.index == VACANT
+                        && entry.generation < GENERATION_MASK
+                        && !listed[slot as usize] =>
+                {
+                    listed[slot as usize] = true;
+                }
+                _ => return None,
+            }
+        }
+        let retired = ids
+            .slots
+            .iter()
+            .filter(|slot| slot.index == VACANT && slot.generation == GENERATION_MASK)
+            .count();
+        let consistent = occupied == particles.len()
+            && occupied + ids.free.len() + retired == slots.len()
+            && particles
+                .iter()
+                .enumerate()
+                .all(|(index, particle)| ids.lookup(particle.id) == Some(index));
+        consistent.then_some(ids)
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ParticleSystem {
+    /// Spawns a particle at `(x, y)` with its velocity, size and orbit drawn
+    /// from the current ranges, returning its id.
+    pub fn add_particle(&mut self, x: f64, y: f64) -> Result<u32, ParticleError> {
+        let x = params::finite("x", x)?;
+        let y = params::finite("y", y)?;
+        let particle = particle_at(&mut self.rng, x, y, &self.ranges);
+        self.insert_particle(particle)
+    }
+
+    /// Like `add_particle`, with an explicit drift velocity.
+    pub fn add_particle_with_velocity(
+        &mut self,
+        x: f64,
+        y: f64,
+        vx: f64,
+        vy: f64,
+    ) -> Result<u32, ParticleError> {
+        let x = params::finite("x", x)?;
+        let y = params::finite("y", y)?;
+        let vx = params::finite("vx", vx)?;
+        let vy = params::finite("vy", vy)?;
+        let mut particle = particle_at(&mut self.rng, x, y, &self.ranges);
+        particle.set_drift(vx, vy);
+        self.insert_particle(particle)
+    }
+
+    /// Removes the particle with `id`. The last particle moves into its
+    /// place, so indices shift but every other id stays valid.
+    pub fn remove_particle(&mut self, id: u32) -> bool {
+        let Some(index) = self.ids.lookup(id) else {
+            return false;
+        };
+
+        self.remove_at(index);
+        self.rebuild_grid();
+        true
+    }
+
+    /// Grows the system with randomly placed particles or drops particles
+    /// from the end of `particle_ids` until there are `count`. Removals swap
+    /// the last particle into the gap, so the end is not necessarily the
+    /// most recently added.
+    pub fn set_particle_count(&mut self, count: usize) -> Result<(), ParticleError> {
+        if count > MAX_PARTICLES {
+            return Err(ParticleError::TooManyParticles);
+        }
+
+        while self.particles.len() > count {
+            if let Some(particle) = self.particles.pop() {
+                self.ids.release(particle.id);
+            }
+        }
+        self.mouse_connections.retain(|&(idx, _)| idx < count);
+
+        while self.particles.len() < count {
+            let mut particle =
+                random_particle(&mut self.rng, self.width, self.height, &self.ranges);
+            particle.id = self.ids.allocate(self.particles.len())?;
+            self.particles.push(particle);
+        }
+
+        self.rebuild_grid();
+        Ok(())
+    }
+
+    pub fn contains_particle(&self, id: u32) -> bool {
+        self.ids.lookup(id).is_some()
+    }
+
+    /// Current position of the particle in `particle_ids` and every
+    /// per-particle output.
+    pub fn particle_index(&self, id: u32) -> Option<usize> {
+        self.ids.lookup(id)
+    }
+
+    pub fn particle(&self, id: u32) -> Option<Particle> {
+        self.ids.lookup(id).map(|index| self.particles[index])
+    }
+
+    /// Ids in the same order as the records of `get_particles` and the
+    /// particle buffer.
+    pub fn particle_ids(&self) -> Vec<u32> {
+        self.particles.iter().map(|particle| particle.id).collect()
+    }
+}
+
+impl ParticleSystem {
+    fn insert_particle(&mut self, particle: Particle) -> Result<u32, ParticleError> {
+        let id = self.push_particle(particle)?;
+        self.rebuild_grid();
+        Ok(id)
+    }
+
+    /// Appends a particle under a fresh id. The grid is left for the caller
+    /// to rebuild.
+    pub(crate) fn push_particle(&mut self, particle: Particle) -> Result<u32, ParticleError> {
+        let id = self.ids.allocate(self.particles.len())?;
+        self.particles.push(Particle { id, ..particle });
+        Ok(id)
+    }
+
+    /// Swap-removes the particle at `index`. The grid is left for the caller
+    /// to rebuild.
+    pub(crate) fn remove_at(&mut self, index: usize) {
+        let removed = self.particles.swap_remove(index);
+        self.ids.release(removed.id);
+        if let Some(moved) = self.particles.get(index) {
+            self.ids.relocate(moved.id, index);
+        }
+
+        // 鼠标连线记录的是索引，需要跟着交换
+        let last = self.particles.len();
+        self.mouse_connections.retain_mut(|(idx, _)| {
+            if *idx == index {
+                return false;
+            }
+            if *idx == last {
+                *idx = index;
+            }
+            true
+        });
+    }
+}
@@ -0,0 +1,135 @@
+use crate::color::{self, Rgba};
+use crate::connections::Link;
+use crate::{Particle, ParticleSystem};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Optional fields appended to each particle record after `x, y, size`, in
+/// declaration order. Combine them as bit flags for `set_particle_fields`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParticleField {
+    /// `age_ms, fade`: time since spawn and the fade-in/out opacity
+    /// multiplier from `Particle::fade`.
+    Age = 1,
+    /// `r, g, b, a` from the colour rules, each in `0..=1`. Also appends the
+    /// blend of both endpoints' colours to every connection record.
+    Color = 2,
+}
+
+const ALL_FIELDS: u32 = ParticleField::Age as u32 | ParticleField::Color as u32;
+
+/// Byte stride and attribute offsets of an interleaved `f32` vertex buffer,
+/// ready for `vertexAttribPointer`. Attributes a buffer lacks are `None`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VertexLayout {
+    pub stride: u32,
+    /// `x, y`.
+    pub position: u32,
+    pub size: Option<u32>,
+    /// `r, g, b`.
+    pub color: u32,
+    pub alpha: u32,
+}
+
+// 实例数据：x, y, size, r, g, b, alpha
+pub(crate) const INSTANCE_FLOATS: usize = 7;
+// 连接线顶点：x, y, r, g, b, alpha
+pub(crate) const LINE_VERTEX_FLOATS: usize = 6;
+
+const F32_BYTES: u32 = 4;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ParticleSystem {
+    pub fn particle_fields(&self) -> u32 {
+        self.particle_fields
+    }
+
+    /// Chooses the optional `ParticleField`s included in `get_particles`,
+    /// `get_interpolated_particles` and the particle buffer. Unknown bits are
+    /// ignored.
+    pub fn set_particle_fields(&mut self, fields: u32) {
+        self.particle_fields = fields & ALL_FIELDS;
+    }
+
+    /// Number of values per particle record with the current fields.
+    pub fn particle_stride(&self) -> usize {
+        let mut stride = 3;
+        if self.has_field(ParticleField::Age) {
+            stride += 2;
+        }
+        if self.has_field(ParticleField::Color) {
+            stride += 4;
+        }
+        stride
+    }
+
+    /// Layout of the WebGL instance buffer: one record per particle, with
+    /// alpha already multiplied by the particle's fade.
+    pub fn instance_layout(&self) -> VertexLayout {
+        VertexLayout {
+            stride: INSTANCE_FLOATS as u32 * F32_BYTES,
+            position: 0,
+            size: Some(2 * F32_BYTES),
+            color: 3 * F32_BYTES,
+            alpha: 6 * F32_BYTES,
+        }
+    }
+
+    /// Layout of the WebGL line vertex buffer: two vertices per segment, each
+    /// with its endpoint's colour and the line's opacity as alpha.
+    pub fn line_vertex_layout(&self) -> VertexLayout {
+        VertexLayout {
+            stride: LINE_VERTEX_FLOATS as u32 * F32_BYTES,
+            position: 0,
+            size: None,
+            color: 2 * F32_BYTES,
+            alpha: 5 * F32_BYTES,
+        }
+    }
+
+    /// Number of values per connection record with the current fields.
+    pub fn connection_stride(&self) -> usize {
+        if self.has_field(ParticleField::Color) {
+            9
+        } else {
+            5
+        }
+    }
+}
+
+impl ParticleSystem {
+    fn has_field(&self, field: ParticleField) -> bool {
+        self.particle_fields & field as u32 != 0
+    }
+
+    /// Writes one particle record at position `(x, y)`.
+    pub(crate) fn write_particle(
+        &self,
+        particle: &Particle,
+        (x, y): (f64, f64),
+        mut push: impl FnMut(f64),
+    ) {
+        push(x);
+        push(y);
+        push(particle.size);
+        if self.has_field(ParticleField::Age) {
+            push(particle.age_ms);
+            push(particle.fade());
+        }
+        if self.has_field(ParticleField::Color) {
+            self.color_of(particle).into_iter().for_each(push);
+        }
+    }
+
+    /// Colour appended to each segment of `link`, if colours are enabled.
+    pub(crate) fn link_color(&self, link: &Link) -> Option<Rgba> {
+        if !self.has_field(ParticleField::Color) {
+            return None;
+        }
+        let a = self.color_of(&self.particles[link.a]);
+        let b = self.color_of(&self.particles[link.b]);
+        Some(color::blend(a, b, 0.5))
+    }
+}
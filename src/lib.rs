@@ -1,98 +1,270 @@
-use rand::Rng;
+//! Floating particle background simulation.
+//!
+//! The simulation itself is plain Rust and can be used natively; the
+//! `wasm` feature (on by default) adds the `wasm-bindgen` exports used from
+//! JavaScript.
+
+mod attractors;
+mod boundary;
+mod buffers;
+mod color;
+mod config;
+mod connections;
+mod emitters;
+mod error;
+mod grid;
+mod ids;
+mod json;
+mod layout;
+mod looping;
+mod params;
+mod physics;
+mod recording;
+mod render;
+mod rng;
+mod snapshot;
+mod svg;
+mod svg_animation;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+use attractors::Attractor;
+pub use attractors::{AttractorKind, Falloff};
+pub use boundary::BoundaryMode;
+use buffers::FrameBuffers;
+pub use color::{ColorMode, ColorRules};
+pub use config::ParticleConfig;
+pub use connections::{ConnectionFormat, ConnectionStats};
+use emitters::Emitter;
+pub use emitters::EmitterShape;
+pub use error::ParticleError;
+use grid::SpatialGrid;
+use ids::ParticleIds;
+pub use layout::{ParticleField, VertexLayout};
+use looping::Looping;
+pub use params::{ParticleRanges, Range};
+pub use physics::MotionModel;
+use rand::{Rng, SeedableRng};
+use recording::Call;
+pub use recording::{Recording, Replayer};
+pub use render::Renderer;
+use rng::Xoshiro256;
+use std::cell::Cell;
 use std::f64;
+pub use svg::{SvgGrouping, SvgOptions};
+pub use svg_animation::SvgAnimation;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
-#[wasm_bindgen]
+const FIXED_STEP_MS: f64 = 1000.0 / 60.0;
+const MAX_CATCH_UP_STEPS: u32 = 5;
+// 捕获后向轨道目标点靠拢的速度，以及进入/离开轨道时的混合速率
+const ORBIT_PULL: f64 = 0.08;
+const ORBIT_CAPTURE_RATE: f64 = 0.05;
+const ORBIT_RELEASE_RATE: f64 = 0.04;
+// 尺寸或速度范围变化后每步向目标值靠近的比例
+const SIZE_EASE: f64 = 0.05;
+const DRIFT_EASE: f64 = 0.05;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct ParticleSystem {
     particles: Vec<Particle>,
+    ids: ParticleIds,
     width: f64,
     height: f64,
     connection_distance: f64,
+    max_connections_per_particle: usize,
+    max_connections: usize,
+    connection_stats: Cell<ConnectionStats>,
+    connection_format: ConnectionFormat,
     mouse_x: f64,
     mouse_y: f64,
     mouse_radius: f64,
     mouse_force: f64,
     mouse_connections: Vec<(usize, f64)>,
-    pub max_attraction_force: f64,
-    pub border_restitution: f64,
+    grid: SpatialGrid,
+    buffers: FrameBuffers,
+    boundary_mode: BoundaryMode,
+    motion_model: MotionModel,
+    linear_damping: f64,
+    velocity_relaxation: f64,
+    max_speed: f64,
+    attractors: Vec<Attractor>,
+    next_attractor_id: u32,
+    emitters: Vec<Emitter>,
+    next_emitter_id: u32,
+    particle_fields: u32,
+    webgl_output: bool,
+    rng: Xoshiro256,
+    seed: u64,
+    accumulator_ms: f64,
+    fixed_step_ms: f64,
+    max_catch_up_steps: u32,
+    alpha: f64,
+    ranges: ParticleRanges,
+    colors: ColorRules,
+    max_attraction_force: f64,
+    border_restitution: f64,
+    looping: Option<Looping>,
+    recording: Option<Recording>,
+    pub orbit_enabled: bool,
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 #[derive(Clone, Copy)]
 pub struct Particle {
+    /// Stable handle that survives other particles being added or removed.
+    pub id: u32,
     pub x: f64,
     pub y: f64,
+    pub prev_x: f64,
+    pub prev_y: f64,
     pub vx: f64,
     pub vy: f64,
     pub size: f64,
+    pub target_size: f64,
     pub base_vx: f64,
     pub base_vy: f64,
+    /// Base drift the particle eases towards after a velocity range change.
+    pub target_vx: f64,
+    pub target_vy: f64,
     pub orbit_angle: f64,
     pub orbit_speed: f64,
     pub orbit_radius: f64,
     pub is_orbiting: bool,
+    pub orbit_blend: f64,
+    /// Simulated time since the particle was spawned.
+    pub age_ms: f64,
+    /// Age at which the particle is removed; infinite for particles that
+    /// live forever.
+    pub lifetime_ms: f64,
+    pub fade_in_ms: f64,
+    pub fade_out_ms: f64,
+    /// Position the periodic loop path is measured from; NaN until the
+    /// particle first moves in looping mode.
+    loop_origin: (f64, f64),
+    /// Drift as drawn from the velocity ranges, before bounces flip or damp
+    /// it; range changes are remapped from this.
+    drift: (f64, f64),
 }
 
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 impl ParticleSystem {
-    #[wasm_bindgen(constructor)]
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
     pub fn new(
         width: f64,
         height: f64,
         num_particles: usize,
         connection_distance: f64,
     ) -> ParticleSystem {
-        let mut rng = rand::thread_rng();
-        let mut particles = Vec::with_capacity(num_particles);
-
-        for _ in 0..num_particles {
-            let x = rng.gen_range(0.0..width);
-            let y = rng.gen_range(0.0..height);
-
-            let base_vx = rng.gen_range(-0.4..0.4);
-            let base_vy = rng.gen_range(-0.4..0.4);
-
-            let particle = Particle {
-                x,
-                y,
-                vx: base_vx,
-                vy: base_vy,
-                size: rng.gen_range(1.0..3.0),
-                base_vx,
-                base_vy,
-                orbit_angle: rng.gen_range(0.0..std::f64::consts::PI * 2.0),
-                orbit_speed: rng.gen_range(0.002..0.008),
-                orbit_radius: rng.gen_range(5.0..60.0),
-                is_orbiting: false,
-            };
-            particles.push(particle);
-        }
+        let seed = rand::thread_rng().gen();
+        ParticleSystem::with_seed(width, height, num_particles, connection_distance, seed)
+    }
 
-        ParticleSystem {
-            particles,
+    /// Builds a system whose layout and subsequent motion are fully
+    /// determined by `seed` and the sequence of calls made on it.
+    pub fn with_seed(
+        width: f64,
+        height: f64,
+        num_particles: usize,
+        connection_distance: f64,
+        seed: u64,
+    ) -> ParticleSystem {
+        let config = ParticleConfig {
             width,
             height,
+            particle_count: num_particles,
             connection_distance,
-            mouse_x: -1000.0,
-            mouse_y: -1000.0,
-            mouse_radius: 150.0,
-            mouse_force: 1.0,
-            mouse_connections: Vec::new(),
-            max_attraction_force: 0.4,
-            border_restitution: 1.0,
-        }
+            ..ParticleConfig::default()
+        };
+        ParticleSystem::build(&config, seed)
     }
+
+    /// Like `with_seed`, from a text seed; see `string_seed`.
+    pub fn with_string_seed(
+        width: f64,
+        height: f64,
+        num_particles: usize,
+        connection_distance: f64,
+        seed: &str,
+    ) -> ParticleSystem {
+        ParticleSystem::with_seed(
+            width,
+            height,
+            num_particles,
+            connection_distance,
+            string_seed(seed),
+        )
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
     pub fn update(&mut self) {
-        let mouse_active = self.mouse_x >= 0.0
-            && self.mouse_y >= 0.0
-            && self.mouse_x <= self.width
-            && self.mouse_y <= self.height;
+        self.record(Call::Update);
+        self.step();
+        self.accumulator_ms = 0.0;
+        self.alpha = 1.0;
+    }
+
+    /// Advances the simulation by `dt_ms` of wall-clock time using fixed-size
+    /// steps, returning the number of steps taken. Time beyond
+    /// `max_catch_up_steps` steps is dropped so a throttled tab resumes
+    /// without a burst of motion.
+    pub fn update_with_dt(&mut self, dt_ms: f64) -> u32 {
+        self.record(Call::UpdateWithDt(dt_ms));
+        if dt_ms.is_finite() && dt_ms > 0.0 {
+            self.accumulator_ms += dt_ms;
+        }
+
+        let mut steps = 0;
+        while self.accumulator_ms >= self.fixed_step_ms && steps < self.max_catch_up_steps {
+            self.step();
+            self.accumulator_ms -= self.fixed_step_ms;
+            steps += 1;
+        }
+        if self.accumulator_ms >= self.fixed_step_ms {
+            self.accumulator_ms %= self.fixed_step_ms;
+        }
+
+        self.alpha = self.accumulator_ms / self.fixed_step_ms;
+        steps
+    }
+
+    pub fn interpolation_alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    fn step(&mut self) {
+        let mouse_active = self.mouse_active();
+        if self.looping.is_some() {
+            let expired = self.advance_loop();
+            self.finish_step(&expired, mouse_active);
+            return;
+        }
+
+        let inertial = self.motion_model == MotionModel::Inertial;
 
         self.mouse_connections.clear();
+        let mut expired = Vec::new();
 
         for (idx, particle) in self.particles.iter_mut().enumerate() {
-            let mut vx = particle.base_vx;
-            let mut vy = particle.base_vy;
+            particle.prev_x = particle.x;
+            particle.prev_y = particle.y;
+
+            let (mut vx, mut vy) = if inertial {
+                let damping = 1.0 - self.linear_damping;
+                let vx = particle.vx * damping;
+                let vy = particle.vy * damping;
+                (
+                    vx + (particle.base_vx - vx) * self.velocity_relaxation,
+                    vy + (particle.base_vy - vy) * self.velocity_relaxation,
+                )
+            } else {
+                (particle.base_vx, particle.base_vy)
+            };
+            let mut captured = false;
 
             if mouse_active {
                 let dx = particle.x - self.mouse_x;
@@ -109,44 +281,142 @@ impl ParticleSystem {
                     let force = attraction_strength * self.max_attraction_force / distance;
                     vx -= dx * force;
                     vy -= dy * force;
+
+                    if self.orbit_enabled {
+                        captured = true;
+                        if !particle.is_orbiting {
+                            particle.orbit_angle = dy.atan2(dx);
+                            particle.is_orbiting = true;
+                        }
+                    }
                 }
             }
 
-            particle.x += vx;
-            particle.y += vy;
+            for attractor in &self.attractors {
+                let (fx, fy) = attractor.force_at(particle.x, particle.y);
+                vx += fx;
+                vy += fy;
+            }
+
+            if captured {
+                particle.orbit_blend += (1.0 - particle.orbit_blend) * ORBIT_CAPTURE_RATE;
+                particle.orbit_angle += particle.orbit_speed;
+
+                let target_x = self.mouse_x + particle.orbit_angle.cos() * particle.orbit_radius;
+                let target_y = self.mouse_y + particle.orbit_angle.sin() * particle.orbit_radius;
+                let orbit_vx = (target_x - particle.x) * ORBIT_PULL;
+                let orbit_vy = (target_y - particle.y) * ORBIT_PULL;
+
+                vx += (orbit_vx - vx) * particle.orbit_blend;
+                vy += (orbit_vy - vy) * particle.orbit_blend;
+            } else if particle.orbit_blend > 0.0 {
+                // 离开鼠标范围后保留部分轨道速度，逐渐回到原本的漂移
+                particle.is_orbiting = false;
+                particle.orbit_blend *= 1.0 - ORBIT_RELEASE_RATE;
+                if particle.orbit_blend < 0.01 {
+                    particle.orbit_blend = 0.0;
+                }
 
-            if particle.x < 0.0 {
-                particle.x = 0.0;
-                particle.base_vx = particle.base_vx.abs() * self.border_restitution;
-            } else if particle.x > self.width {
-                particle.x = self.width;
-                particle.base_vx = -particle.base_vx.abs() * self.border_restitution;
+                // 惯性模式下速度本身带有动量，无需额外保留
+                if !inertial {
+                    let keep = 1.0 - ORBIT_RELEASE_RATE;
+                    vx += (particle.vx - vx) * keep;
+                    vy += (particle.vy - vy) * keep;
+                }
             }
 
-            if particle.y < 0.0 {
-                particle.y = 0.0;
-                particle.base_vy = particle.base_vy.abs() * self.border_restitution;
-            } else if particle.y > self.height {
-                particle.y = self.height;
-                particle.base_vy = -particle.base_vy.abs() * self.border_restitution;
+            if inertial {
+                (vx, vy) = physics::clamp_speed(vx, vy, self.max_speed);
             }
 
+            particle.x += vx;
+            particle.y += vy;
+
             particle.vx = vx;
             particle.vy = vy;
+
+            particle.ease_size();
+            particle.ease_drift();
+
+            let replaced = boundary::apply(
+                self.boundary_mode,
+                particle,
+                self.width,
+                self.height,
+                self.border_restitution,
+                &self.ranges,
+                &mut self.rng,
+            );
+            if replaced {
+                // 被吸收后补上的是新粒子，旧 ID 不再指向它
+                match self.ids.renew(particle.id) {
+                    Ok(id) => particle.id = id,
+                    // ID 已用尽，新粒子无法登记，直接移除
+                    Err(_) => {
+                        expired.push(idx);
+                        continue;
+                    }
+                }
+            }
+
+            particle.age_ms += self.fixed_step_ms;
+            if particle.age_ms >= particle.lifetime_ms {
+                expired.push(idx);
+            }
+        }
+
+        self.finish_step(&expired, mouse_active);
+    }
+
+    /// Removes expired particles and advances everything that does not
+    /// depend on how particles moved.
+    fn finish_step(&mut self, expired: &[usize], mouse_active: bool) {
+        // 从后往前删除，换到前面的粒子总是存活的
+        for &idx in expired.iter().rev() {
+            self.remove_at(idx);
         }
+
+        self.age_attractors(self.fixed_step_ms);
+        self.run_emitters(self.fixed_step_ms, mouse_active);
+        self.rebuild_grid();
     }
+
+    fn rebuild_grid(&mut self) {
+        self.grid.rebuild(
+            &self.particles,
+            self.width,
+            self.height,
+            self.connection_distance,
+        );
+    }
+    pub fn boundary_mode(&self) -> BoundaryMode {
+        self.boundary_mode
+    }
+
+    pub fn set_boundary_mode(&mut self, mode: BoundaryMode) {
+        self.boundary_mode = mode;
+    }
+
+    pub fn motion_model(&self) -> MotionModel {
+        self.motion_model
+    }
+
+    pub fn set_motion_model(&mut self, model: MotionModel) {
+        self.motion_model = model;
+    }
+
     pub fn update_mouse_position(&mut self, x: f64, y: f64) {
+        self.record(Call::UpdateMousePosition(x, y));
         self.mouse_x = x;
         self.mouse_y = y;
     }
 
-    pub fn set_mouse_force(&mut self, force: f64) {
-        self.mouse_force = force;
-    }
-
-    pub fn resize(&mut self, width: f64, height: f64) {
+    pub fn resize(&mut self, width: f64, height: f64) -> Result<(), ParticleError> {
+        let width = params::positive("width", width)?;
+        let height = params::positive("height", height)?;
         self.width = width;
         self.height = height;
+        self.record(Call::Resize(width, height));
 
         for particle in &mut self.particles {
             if particle.x > width {
@@ -155,33 +425,53 @@ impl ParticleSystem {
             if particle.y > height {
                 particle.y = height;
             }
+            particle.prev_x = particle.prev_x.min(width);
+            particle.prev_y = particle.prev_y.min(height);
         }
+
+        self.rebuild_grid();
+        Ok(())
+    }
+}
+
+impl ParticleSystem {
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
     }
 
-    pub fn get_particles(&self) -> js_sys::Float64Array {
-        let array_len = self.particles.len() * 3;
-        let result = js_sys::Float64Array::new_with_length(array_len as u32);
+    /// Particles as `x, y, size` records, followed by any fields enabled
+    /// with `set_particle_fields`.
+    pub fn particle_data(&self) -> Vec<f64> {
+        let mut data = Vec::with_capacity(self.particles.len() * self.particle_stride());
 
-        for (i, particle) in self.particles.iter().enumerate() {
-            let base_idx = i * 3;
-            result.set_index(base_idx as u32, particle.x);
-            result.set_index((base_idx + 1) as u32, particle.y);
-            result.set_index((base_idx + 2) as u32, particle.size);
+        for particle in &self.particles {
+            self.write_particle(particle, (particle.x, particle.y), |value| data.push(value));
         }
 
-        result
+        data
     }
 
-    pub fn get_mouse_connections(&self) -> js_sys::Float64Array {
+    /// Like `particle_data`, but positions are blended between the last two
+    /// fixed steps by `interpolation_alpha`.
+    pub fn interpolated_particle_data(&self) -> Vec<f64> {
+        let mut data = Vec::with_capacity(self.particles.len() * self.particle_stride());
+
+        for particle in &self.particles {
+            let position = particle.position_at(self.alpha);
+            self.write_particle(particle, position, |value| data.push(value));
+        }
+
+        data
+    }
+
+    /// Particles pulled by the mouse as `x, y, strength` records.
+    pub fn mouse_connection_data(&self) -> Vec<f64> {
         let mut connections = Vec::new();
 
         for (particle_idx, strength) in &self.mouse_connections {
             let particle = self.particles[*particle_idx];
 
-            let dx = (particle.x - self.mouse_x).abs();
-            let dy = (particle.y - self.mouse_y).abs();
-
-            if dx > self.width / 2.0 || dy > self.height / 2.0 {
+            if !self.mouse_connection_visible(&particle) {
                 continue;
             }
 
@@ -190,57 +480,217 @@ impl ParticleSystem {
             connections.push(*strength);
         }
 
-        let result = js_sys::Float64Array::new_with_length(connections.len() as u32);
-        for (i, value) in connections.iter().enumerate() {
-            result.set_index(i as u32, *value);
-        }
-
-        result
+        connections
     }
 
-    pub fn calculate_connections(&self) -> js_sys::Float64Array {
-        let mut connections = Vec::new();
-
-        for i in 0..self.particles.len() {
-            let p1 = self.particles[i];
+    fn build(config: &ParticleConfig, seed: u64) -> ParticleSystem {
+        let mut rng = Xoshiro256::seed_from_u64(seed);
+        let count = config.particle_count.min(ids::MAX_PARTICLES);
+        let mut particles = Vec::with_capacity(count);
+        let mut ids = ParticleIds::default();
 
-            for j in (i + 1)..self.particles.len() {
-                let p2 = self.particles[j];
+        for index in 0..count {
+            let mut particle =
+                random_particle(&mut rng, config.width, config.height, &config.ranges);
+            particle.id = ids.allocate(index).expect("count is within MAX_PARTICLES");
+            particles.push(particle);
+        }
 
-                let dx = (p1.x - p2.x).abs();
-                let dy = (p1.y - p2.y).abs();
-                // 跳过屏幕两端的粒子
-                if dx > self.width / 2.0 || dy > self.height / 2.0 {
-                    continue;
-                }
+        let mut system = ParticleSystem {
+            particles,
+            ids,
+            width: config.width,
+            height: config.height,
+            connection_distance: config.connection_distance,
+            max_connections_per_particle: config.max_connections_per_particle,
+            max_connections: config.max_connections,
+            connection_stats: Cell::default(),
+            connection_format: ConnectionFormat::Segments,
+            mouse_x: -1000.0,
+            mouse_y: -1000.0,
+            mouse_radius: config.mouse_radius,
+            mouse_force: config.mouse_force,
+            mouse_connections: Vec::new(),
+            grid: SpatialGrid::new(),
+            buffers: FrameBuffers::default(),
+            boundary_mode: config.boundary_mode,
+            motion_model: config.motion_model,
+            linear_damping: config.linear_damping,
+            velocity_relaxation: config.velocity_relaxation,
+            max_speed: config.max_speed,
+            attractors: Vec::new(),
+            next_attractor_id: 1,
+            emitters: Vec::new(),
+            next_emitter_id: 1,
+            particle_fields: 0,
+            webgl_output: false,
+            rng,
+            seed,
+            accumulator_ms: 0.0,
+            fixed_step_ms: config.fixed_timestep_ms,
+            max_catch_up_steps: config.max_catch_up_steps,
+            alpha: 1.0,
+            ranges: config.ranges,
+            colors: config.color.clone(),
+            max_attraction_force: config.max_attraction_force,
+            border_restitution: config.border_restitution,
+            looping: Looping::new(config.loop_period_ms),
+            recording: None,
+            orbit_enabled: config.orbit_enabled,
+        };
+        system.rebuild_grid();
+        system
+    }
 
-                let distance = (dx * dx + dy * dy).sqrt();
+    fn mouse_active(&self) -> bool {
+        self.mouse_x >= 0.0
+            && self.mouse_y >= 0.0
+            && self.mouse_x <= self.width
+            && self.mouse_y <= self.height
+    }
 
-                if distance < self.connection_distance {
-                    let opacity = 1.0 - (distance / self.connection_distance);
+    fn mouse_connection_visible(&self, particle: &Particle) -> bool {
+        let dx = (particle.x - self.mouse_x).abs();
+        let dy = (particle.y - self.mouse_y).abs();
 
-                    let d1 = ((p1.x - self.mouse_x).powi(2) + (p1.y - self.mouse_y).powi(2)).sqrt();
-                    let d2 = ((p2.x - self.mouse_x).powi(2) + (p2.y - self.mouse_y).powi(2)).sqrt();
+        dx <= self.width / 2.0 && dy <= self.height / 2.0
+    }
+}
 
-                    let mut final_opacity = opacity;
-                    if d1 < self.mouse_radius || d2 < self.mouse_radius {
-                        final_opacity *= 1.3; // 稍微增强鼠标附近的连接线
-                    }
+impl Particle {
+    /// Opacity multiplier from fading in after birth and out before death,
+    /// between 0 and 1.
+    pub fn fade(&self) -> f64 {
+        let mut fade = 1.0;
+        if self.fade_in_ms > 0.0 && self.age_ms < self.fade_in_ms {
+            fade *= self.age_ms / self.fade_in_ms;
+        }
+        let remaining = self.lifetime_ms - self.age_ms;
+        if self.fade_out_ms > 0.0 && remaining < self.fade_out_ms {
+            fade *= (remaining / self.fade_out_ms).max(0.0);
+        }
+        fade
+    }
 
-                    connections.push(p1.x);
-                    connections.push(p1.y);
-                    connections.push(p2.x);
-                    connections.push(p2.y);
-                    connections.push(final_opacity);
-                }
+    fn ease_size(&mut self) {
+        if self.size != self.target_size {
+            self.size += (self.target_size - self.size) * SIZE_EASE;
+            if (self.target_size - self.size).abs() < 0.01 {
+                self.size = self.target_size;
             }
         }
+    }
 
-        let result = js_sys::Float64Array::new_with_length(connections.len() as u32);
-        for (i, value) in connections.iter().enumerate() {
-            result.set_index(i as u32, *value);
+    fn ease_drift(&mut self) {
+        if (self.base_vx, self.base_vy) != (self.target_vx, self.target_vy) {
+            self.base_vx += (self.target_vx - self.base_vx) * DRIFT_EASE;
+            self.base_vy += (self.target_vy - self.base_vy) * DRIFT_EASE;
+            if (self.target_vx - self.base_vx).abs() < 1e-3
+                && (self.target_vy - self.base_vy).abs() < 1e-3
+            {
+                self.base_vx = self.target_vx;
+                self.base_vy = self.target_vy;
+            }
         }
+    }
+
+    /// Gives the particle an explicit drift velocity, replacing the one
+    /// drawn from the ranges.
+    pub(crate) fn set_drift(&mut self, vx: f64, vy: f64) {
+        self.vx = vx;
+        self.vy = vy;
+        self.base_vx = vx;
+        self.base_vy = vy;
+        self.target_vx = vx;
+        self.target_vy = vy;
+        self.drift = (vx, vy);
+    }
+
+    pub(crate) fn position_at(&self, alpha: f64) -> (f64, f64) {
+        (
+            self.prev_x + (self.x - self.prev_x) * alpha,
+            self.prev_y + (self.y - self.prev_y) * alpha,
+        )
+    }
+}
+
+pub(crate) fn random_particle(
+    rng: &mut Xoshiro256,
+    width: f64,
+    height: f64,
+    ranges: &ParticleRanges,
+) -> Particle {
+    let x = random_coordinate(rng, width);
+    let y = random_coordinate(rng, height);
+    particle_at(rng, x, y, ranges)
+}
+
+/// A particle at `(x, y)` with its other properties drawn from `ranges`. The
+/// id is left for the caller to assign.
+pub(crate) fn particle_at(
+    rng: &mut Xoshiro256,
+    x: f64,
+    y: f64,
+    ranges: &ParticleRanges,
+) -> Particle {
+    let base_vx = ranges.velocity_x.sample(rng);
+    let base_vy = ranges.velocity_y.sample(rng);
+    let size = ranges.size.sample(rng);
+
+    Particle {
+        id: 0,
+        x,
+        y,
+        prev_x: x,
+        prev_y: y,
+        vx: base_vx,
+        vy: base_vy,
+        size,
+        target_size: size,
+        base_vx,
+        base_vy,
+        target_vx: base_vx,
+        target_vy: base_vy,
+        orbit_angle: rng.gen_range(0.0..std::f64::consts::PI * 2.0),
+        orbit_speed: ranges.orbit_speed.sample(rng),
+        orbit_radius: ranges.orbit_radius.sample(rng),
+        is_orbiting: false,
+        orbit_blend: 0.0,
+        age_ms: 0.0,
+        lifetime_ms: f64::INFINITY,
+        fade_in_ms: 0.0,
+        fade_out_ms: 0.0,
+        loop_origin: (f64::NAN, f64::NAN),
+        drift: (base_vx, base_vy),
+    }
+}
+
+fn random_coordinate(rng: &mut Xoshiro256, extent: f64) -> f64 {
+    if extent > 0.0 {
+        rng.gen_range(0.0..extent)
+    } else {
+        0.0
+    }
+}
+
+/// Seed for a text seed, the same for every entry point: a string of
+/// decimal digits that fits in a `u64` is that number, anything else is
+/// hashed.
+pub(crate) fn string_seed(seed: &str) -> u64 {
+    // 配置 JSON 会把超出 2^53 的种子写成数字字符串，必须按整数读回
+    let digits = !seed.is_empty() && seed.bytes().all(|byte| byte.is_ascii_digit());
+    match seed.parse() {
+        Ok(number) if digits => number,
+        _ => hash_seed(seed),
+    }
+}
 
-        result
+// FNV-1a，保证字符串种子在不同平台和编译器版本下得到相同的结果
+fn hash_seed(seed: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in seed.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
     }
+    hash
 }
@@ -0,0 +1,138 @@
+use crate::color;
+use crate::params;
+use crate::{ParticleError, ParticleSystem};
+use std::f64::consts::TAU;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// 每个粒子偏离匀速直线的最大距离，像素
+const WANDER_RADIUS: f64 = 24.0;
+// 区分 x、y 两个轴的相位
+const PHASE_SALT: [u64; 2] = [0x6c6f_6f70_5f78, 0x6c6f_6f70_5f79];
+
+/// Periodic motion that returns every particle to where it started after
+/// a whole number of steps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Looping {
+    pub period_ms: f64,
+    /// Steps taken since the start of the current loop.
+    pub step: u64,
+    /// Loop length in steps and the canvas size the particles' origins were
+    /// taken for; origins are taken again when either changes.
+    pub anchor: Option<(u64, f64, f64)>,
+}
+
+impl Looping {
+    pub fn new(period_ms: f64) -> Option<Looping> {
+        (period_ms > 0.0).then_some(Looping {
+            period_ms,
+            step: 0,
+            anchor: None,
+        })
+    }
+
+    fn steps(&self, step_ms: f64) -> u64 {
+        ((self.period_ms / step_ms).round() as u64).max(1)
+    }
+}
+
+/// Offset along one axis after `step` of `steps` steps. The drift speed is
+/// rounded to a whole number of trips across the canvas per loop, and the
+/// remainder becomes a sine wave with whole periods, so the offset at
+/// `steps` is a multiple of `extent` and the particle ends where it began.
+fn path(velocity: f64, extent: f64, phase: f64, step: u64, steps: u64) -> f64 {
+    let steps = steps as f64;
+    let t = step as f64 / steps;
+    let distance = velocity * steps;
+    let windings = (distance / extent).round();
+    let residual = distance - windings * extent;
+    let harmonic = (residual.abs() / (TAU * WANDER_RADIUS)).ceil().max(1.0);
+    // 振幅使曲线起点的速度与剩余的漂移速度一致
+    let amplitude = residual / (TAU * harmonic);
+    windings * extent * t + amplitude * ((TAU * harmonic * t + phase).sin() - phase.sin())
+}
+
+fn phase(seed: u64, id: u32, axis: usize) -> f64 {
+    let hash = color::mix(seed ^ u64::from(id) ^ PHASE_SALT[axis]);
+    (hash >> 11) as f64 / (1u64 << 53) as f64 * TAU
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ParticleSystem {
+    /// Length of the seamless loop; `0` when looping is off.
+    pub fn loop_period_ms(&self) -> f64 {
+        self.looping.map_or(0.0, |looping| looping.period_ms)
+    }
+
+    /// Replaces the physics with periodic drift on a torus: after
+    /// `period_ms`, rounded to whole timesteps, every particle is back
+    /// where the loop started, so recordings of one period loop without a
+    /// seam. The mouse and attractors have no effect while looping, and
+    /// emitters and finite lifetimes break the loop. `0` turns it off.
+    pub fn set_loop_period_ms(&mut self, period_ms: f64) -> Result<(), ParticleError> {
+        let period_ms = params::non_negative("loop_period_ms", period_ms)?;
+        self.looping = Looping::new(period_ms);
+        Ok(())
+    }
+
+    /// Position within the current loop, from `0` up to but excluding `1`.
+    pub fn loop_progress(&self) -> f64 {
+        self.looping.map_or(0.0, |looping| {
+            looping.step as f64 / looping.steps(self.fixed_step_ms) as f64
+        })
+    }
+}
+
+impl ParticleSystem {
+    /// One step of looping motion. Returns the indices of particles that
+    /// reached the end of their lifetime.
+    pub(crate) fn advance_loop(&mut self) -> Vec<usize> {
+        let Some(looping) = self.looping.as_mut() else {
+            return Vec::new();
+        };
+        let steps = looping.steps(self.fixed_step_ms);
+        let anchor = (steps, self.width, self.height);
+        if looping.anchor != Some(anchor) {
+            looping.anchor = Some(anchor);
+            looping.step = 0;
+            for particle in &mut self.particles {
+                particle.loop_origin = (f64::NAN, f64::NAN);
+            }
+        }
+        let from = looping.step;
+        let to = (from + 1) % steps;
+        looping.step = to;
+
+        self.mouse_connections.clear();
+        let mut expired = Vec::new();
+        for (idx, particle) in self.particles.iter_mut().enumerate() {
+            let phases = [0, 1].map(|axis| phase(self.seed, particle.id, axis));
+            let x_at = |step| path(particle.base_vx, self.width, phases[0], step, steps);
+            let y_at = |step| path(particle.base_vy, self.height, phases[1], step, steps);
+
+            // 新粒子从当前位置加入循环
+            if particle.loop_origin.0.is_nan() {
+                particle.loop_origin = (particle.x - x_at(from), particle.y - y_at(from));
+            }
+            let (origin_x, origin_y) = particle.loop_origin;
+
+            particle.vx = x_at(from + 1) - x_at(from);
+            particle.vy = y_at(from + 1) - y_at(from);
+            particle.x = (origin_x + x_at(to)).rem_euclid(self.width);
+            particle.y = (origin_y + y_at(to)).rem_euclid(self.height);
+            particle.prev_x = particle.x - particle.vx;
+            particle.prev_y = particle.y - particle.vy;
+            particle.is_orbiting = false;
+            particle.orbit_blend = 0.0;
+
+            // 轨迹由基础速度决定，中途改变会跳位，速度缓动留到退出循环后
+            particle.ease_size();
+
+            particle.age_ms += self.fixed_step_ms;
+            if particle.age_ms >= particle.lifetime_ms {
+                expired.push(idx);
+            }
+        }
+        expired
+    }
+}
@@ -0,0 +1,413 @@
+use crate::recording::Call;
+use crate::rng::Xoshiro256;
+use crate::{ParticleError, ParticleSystem};
+use rand::Rng;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// A closed `[min, max]` interval particle properties are drawn from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Range {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Range {
+    pub const fn new(min: f64, max: f64) -> Range {
+        Range { min, max }
+    }
+
+    pub fn validate(self, name: &'static str) -> Result<Range, ParticleError> {
+        if self.min.is_finite() && self.max.is_finite() && self.min <= self.max {
+            Ok(self)
+        } else {
+            Err(ParticleError::InvalidRange {
+                name,
+                min: self.min,
+                max: self.max,
+            })
+        }
+    }
+
+    pub(crate) fn sample(&self, rng: &mut Xoshiro256) -> f64 {
+        if self.max > self.min {
+            rng.gen_range(self.min..self.max)
+        } else {
+            self.min
+        }
+    }
+
+    /// Moves `value` to the same relative position in `self` that it had in
+    /// `from`, so re-ranged particles keep their spread.
+    pub(crate) fn remap(&self, value: f64, from: Range) -> f64 {
+        let span = from.max - from.min;
+        let t = if span > 0.0 {
+            ((value - from.min) / span).clamp(0.0, 1.0)
+        } else {
+            0.5
+        };
+        self.min + t * (self.max - self.min)
+    }
+
+    fn to_vec(self) -> Vec<f64> {
+        vec![self.min, self.max]
+    }
+}
+
+/// Ranges new particles draw their randomized properties from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParticleRanges {
+    /// Horizontal base drift velocity, in pixels per step.
+    pub velocity_x: Range,
+    /// Vertical base drift velocity, in pixels per step.
+    pub velocity_y: Range,
+    pub size: Range,
+    /// Orbit angular speed, in radians per step.
+    pub orbit_speed: Range,
+    pub orbit_radius: Range,
+}
+
+impl Default for ParticleRanges {
+    fn default() -> ParticleRanges {
+        ParticleRanges {
+            velocity_x: Range::new(-0.4, 0.4),
+            velocity_y: Range::new(-0.4, 0.4),
+            size: Range::new(1.0, 3.0),
+            orbit_speed: Range::new(0.002, 0.008),
+            orbit_radius: Range::new(5.0, 60.0),
+        }
+    }
+}
+
+impl ParticleRanges {
+    pub fn validate(self) -> Result<ParticleRanges, ParticleError> {
+        self.velocity_x.validate("velocity_x_range")?;
+        self.velocity_y.validate("velocity_y_range")?;
+        self.size.validate("size_range")?;
+        self.orbit_speed.validate("orbit_speed_range")?;
+        self.orbit_radius.validate("orbit_radius_range")?;
+        if self.size.min < 0.0 {
+            return Err(invalid("size_range min", self.size.min, "non-negative"));
+        }
+        if self.orbit_radius.min < 0.0 {
+            return Err(invalid(
+                "orbit_radius_range min",
+                self.orbit_radius.min,
+                "non-negative",
+            ));
+        }
+        Ok(self)
+    }
+}
+
+fn invalid(name: &'static str, value: f64, expected: &'static str) -> ParticleError {
+    ParticleError::InvalidValue {
+        name,
+        value,
+        expected,
+    }
+}
+
+/// Remaps a particle's drawn drift into `range` and points its target drift
+/// the same way it currently travels.
+fn retarget(drift: &mut f64, target: &mut f64, range: Range, from: Range) {
+    // 反弹会翻转并衰减基础速度，因此从原始采样值重新映射
+    let flipped = *target * *drift < 0.0;
+    *drift = range.remap(*drift, from);
+    *target = if flipped { -*drift } else { *drift };
+}
+
+pub(crate) fn finite(name: &'static str, value: f64) -> Result<f64, ParticleError> {
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(invalid(name, value, "a finite number"))
+    }
+}
+
+pub(crate) fn positive(name: &'static str, value: f64) -> Result<f64, ParticleError> {
+    if value.is_finite() && value > 0.0 {
+        Ok(value)
+    } else {
+        Err(invalid(name, value, "a positive finite number"))
+    }
+}
+
+pub(crate) fn non_negative(name: &'static str, value: f64) -> Result<f64, ParticleError> {
+    if value.is_finite() && value >= 0.0 {
+        Ok(value)
+    } else {
+        Err(invalid(name, value, "a non-negative finite number"))
+    }
+}
+
+pub(crate) fn fraction(name: &'static str, value: f64) -> Result<f64, ParticleError> {
+    if (0.0..=1.0).contains(&value) {
+        Ok(value)
+    } else {
+        Err(invalid(name, value, "between 0 and 1"))
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ParticleSystem {
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn connection_distance(&self) -> f64 {
+        self.connection_distance
+    }
+
+    /// Maximum length of a connection line; `0` disables connections.
+    pub fn set_connection_distance(&mut self, distance: f64) -> Result<(), ParticleError> {
+        self.connection_distance = non_negative("connection_distance", distance)?;
+        self.rebuild_grid();
+        Ok(())
+    }
+
+    pub fn max_connections_per_particle(&self) -> usize {
+        self.max_connections_per_particle
+    }
+
+    /// Keeps only each particle's nearest `count` connections; `0` removes
+    /// the limit.
+    pub fn set_max_connections_per_particle(&mut self, count: usize) {
+        self.max_connections_per_particle = count;
+    }
+
+    pub fn max_connections(&self) -> usize {
+        self.max_connections
+    }
+
+    /// Caps the connection lines drawn per frame, keeping the most opaque;
+    /// `0` removes the limit.
+    pub fn set_max_connections(&mut self, count: usize) {
+        self.max_connections = count;
+    }
+
+    pub fn mouse_x(&self) -> f64 {
+        self.mouse_x
+    }
+
+    pub fn mouse_y(&self) -> f64 {
+        self.mouse_y
+    }
+
+    pub fn mouse_radius(&self) -> f64 {
+        self.mouse_radius
+    }
+
+    pub fn set_mouse_radius(&mut self, radius: f64) -> Result<(), ParticleError> {
+        self.mouse_radius = non_negative("mouse_radius", radius)?;
+        Ok(())
+    }
+
+    pub fn mouse_force(&self) -> f64 {
+        self.mouse_force
+    }
+
+    /// Scales the mouse attraction; negative values repel.
+    pub fn set_mouse_force(&mut self, force: f64) -> Result<(), ParticleError> {
+        self.mouse_force = finite("mouse_force", force)?;
+        self.record(Call::SetMouseForce(force));
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn max_attraction_force(&self) -> f64 {
+        self.max_attraction_force
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(setter))]
+    pub fn set_max_attraction_force(&mut self, force: f64) -> Result<(), ParticleError> {
+        self.max_attraction_force = non_negative("max_attraction_force", force)?;
+        Ok(())
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(getter))]
+    pub fn border_restitution(&self) -> f64 {
+        self.border_restitution
+    }
+
+    #[cfg_attr(feature = "wasm", wasm_bindgen(setter))]
+    pub fn set_border_restitution(&mut self, restitution: f64) -> Result<(), ParticleError> {
+        self.border_restitution = non_negative("border_restitution", restitution)?;
+        Ok(())
+    }
+
+    pub fn fixed_timestep(&self) -> f64 {
+        self.fixed_step_ms
+    }
+
+    pub fn set_fixed_timestep(&mut self, step_ms: f64) -> Result<(), ParticleError> {
+        let step_ms = positive("fixed_timestep", step_ms)?;
+        self.fixed_step_ms = step_ms;
+        self.accumulator_ms = self.accumulator_ms.min(step_ms);
+        self.alpha = self.accumulator_ms / step_ms;
+        Ok(())
+    }
+
+    pub fn max_catch_up_steps(&self) -> u32 {
+        self.max_catch_up_steps
+    }
+
+    pub fn set_max_catch_up_steps(&mut self, steps: u32) -> Result<(), ParticleError> {
+        if steps == 0 {
+            return Err(invalid("max_catch_up_steps", 0.0, "at least 1"));
+        }
+        self.max_catch_up_steps = steps;
+        Ok(())
+    }
+
+    pub fn linear_damping(&self) -> f64 {
+        self.linear_damping
+    }
+
+    /// Fraction of velocity lost per step in inertial mode.
+    pub fn set_linear_damping(&mut self, damping: f64) -> Result<(), ParticleError> {
+        self.linear_damping = fraction("linear_damping", damping)?;
+        Ok(())
+    }
+
+    pub fn velocity_relaxation(&self) -> f64 {
+        self.velocity_relaxation
+    }
+
+    /// Fraction of the gap to the base drift closed per step in inertial
+    /// mode.
+    pub fn set_velocity_relaxation(&mut self, relaxation: f64) -> Result<(), ParticleError> {
+        self.velocity_relaxation = fraction("velocity_relaxation", relaxation)?;
+        Ok(())
+    }
+
+    pub fn max_speed(&self) -> f64 {
+        self.max_speed
+    }
+
+    pub fn set_max_speed(&mut self, max_speed: f64) -> Result<(), ParticleError> {
+        self.max_speed = positive("max_speed", max_speed)?;
+        Ok(())
+    }
+
+    pub fn velocity_x_range(&self) -> Vec<f64> {
+        self.ranges.velocity_x.to_vec()
+    }
+
+    /// Changes the horizontal drift range; existing particles ease towards
+    /// the same relative speed within the new range.
+    pub fn set_velocity_x_range(&mut self, min: f64, max: f64) -> Result<(), ParticleError> {
+        let range = Range::new(min, max);
+        let old = self.replace_ranges(ParticleRanges {
+            velocity_x: range,
+            ..self.ranges
+        })?;
+        for particle in &mut self.particles {
+            retarget(
+                &mut particle.drift.0,
+                &mut particle.target_vx,
+                range,
+                old.velocity_x,
+            );
+        }
+        Ok(())
+    }
+
+    pub fn velocity_y_range(&self) -> Vec<f64> {
+        self.ranges.velocity_y.to_vec()
+    }
+
+    pub fn set_velocity_y_range(&mut self, min: f64, max: f64) -> Result<(), ParticleError> {
+        let range = Range::new(min, max);
+        let old = self.replace_ranges(ParticleRanges {
+            velocity_y: range,
+            ..self.ranges
+        })?;
+        for particle in &mut self.particles {
+            retarget(
+                &mut particle.drift.1,
+                &mut particle.target_vy,
+                range,
+                old.velocity_y,
+            );
+        }
+        Ok(())
+    }
+
+    /// Sets the same drift range on both axes.
+    pub fn set_velocity_range(&mut self, min: f64, max: f64) -> Result<(), ParticleError> {
+        Range::new(min, max).validate("velocity_range")?;
+        self.set_velocity_x_range(min, max)?;
+        self.set_velocity_y_range(min, max)
+    }
+
+    pub fn size_range(&self) -> Vec<f64> {
+        self.ranges.size.to_vec()
+    }
+
+    /// Changes the size range; existing particles ease towards their
+    /// re-ranged size over the next few steps.
+    pub fn set_size_range(&mut self, min: f64, max: f64) -> Result<(), ParticleError> {
+        let range = Range::new(min, max);
+        let old = self.replace_ranges(ParticleRanges {
+            size: range,
+            ..self.ranges
+        })?;
+        for particle in &mut self.particles {
+            particle.target_size = range.remap(particle.target_size, old.size);
+        }
+        Ok(())
+    }
+
+    pub fn orbit_speed_range(&self) -> Vec<f64> {
+        self.ranges.orbit_speed.to_vec()
+    }
+
+    pub fn set_orbit_speed_range(&mut self, min: f64, max: f64) -> Result<(), ParticleError> {
+        let range = Range::new(min, max);
+        let old = self.replace_ranges(ParticleRanges {
+            orbit_speed: range,
+            ..self.ranges
+        })?;
+        for particle in &mut self.particles {
+            particle.orbit_speed = range.remap(particle.orbit_speed, old.orbit_speed);
+        }
+        Ok(())
+    }
+
+    pub fn orbit_radius_range(&self) -> Vec<f64> {
+        self.ranges.orbit_radius.to_vec()
+    }
+
+    /// Orbiting particles glide to their new radius rather than jumping,
+    /// since they steer towards their orbit target each step.
+    pub fn set_orbit_radius_range(&mut self, min: f64, max: f64) -> Result<(), ParticleError> {
+        let range = Range::new(min, max);
+        let old = self.replace_ranges(ParticleRanges {
+            orbit_radius: range,
+            ..self.ranges
+        })?;
+        for particle in &mut self.particles {
+            particle.orbit_radius = range.remap(particle.orbit_radius, old.orbit_radius);
+        }
+        Ok(())
+    }
+}
+
+impl ParticleSystem {
+    pub fn ranges(&self) -> ParticleRanges {
+        self.ranges
+    }
+
+    fn replace_ranges(&mut self, ranges: ParticleRanges) -> Result<ParticleRanges, ParticleError> {
+        Ok(std::mem::replace(&mut self.ranges, ranges.validate()?))
+    }
+}
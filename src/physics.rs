@@ -0,0 +1,28 @@
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+pub(crate) const DEFAULT_LINEAR_DAMPING: f64 = 0.01;
+pub(crate) const DEFAULT_VELOCITY_RELAXATION: f64 = 0.04;
+pub(crate) const DEFAULT_MAX_SPEED: f64 = 6.0;
+
+/// How forces act on particle velocity.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MotionModel {
+    /// Velocity is rebuilt from the base drift every step; forces only
+    /// displace particles while they are applied.
+    Kinematic = 0,
+    /// Forces accumulate into velocity, which is damped and relaxes back
+    /// towards the base drift.
+    Inertial = 1,
+}
+
+pub(crate) fn clamp_speed(vx: f64, vy: f64, max_speed: f64) -> (f64, f64) {
+    let speed_sq = vx * vx + vy * vy;
+    if speed_sq > max_speed * max_speed {
+        let scale = max_speed / speed_sq.sqrt();
+        (vx * scale, vy * scale)
+    } else {
+        (vx, vy)
+    }
+}
@@ -0,0 +1,245 @@
+use crate::json::Json;
+use crate::snapshot::{self, Container};
+use crate::{ParticleError, ParticleSystem};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+const RECORDING: Container = Container::new(
+    b"FPRC",
+    "floating-particles-recording",
+    "recording",
+    ParticleError::Recording,
+);
+
+/// A call captured by the recorder, with its arguments.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Call {
+    Update,
+    UpdateWithDt(f64),
+    UpdateMousePosition(f64, f64),
+    SetMouseForce(f64),
+    Resize(f64, f64),
+}
+
+impl Call {
+    fn to_json(self) -> Json {
+        let (name, args) = match self {
+            Call::Update => ("update", vec![]),
+            Call::UpdateWithDt(dt_ms) => ("update_with_dt", vec![dt_ms]),
+            Call::UpdateMousePosition(x, y) => ("update_mouse_position", vec![x, y]),
+            Call::SetMouseForce(force) => ("set_mouse_force", vec![force]),
+            Call::Resize(width, height) => ("resize", vec![width, height]),
+        };
+        let mut values = vec![name.into()];
+        values.extend(args.into_iter().map(snapshot::float));
+        Json::Array(values)
+    }
+
+    fn from_json(value: &Json) -> Option<Call> {
+        let [name, args @ ..] = value.as_array()? else {
+            return None;
+        };
+        let args: Vec<f64> = args
+            .iter()
+            .map(snapshot::float_value)
+            .collect::<Option<_>>()?;
+        Some(match (name.as_str()?, args.as_slice()) {
+            ("update", []) => Call::Update,
+            ("update_with_dt", &[dt_ms]) => Call::UpdateWithDt(dt_ms),
+            ("update_mouse_position", &[x, y]) => Call::UpdateMousePosition(x, y),
+            ("set_mouse_force", &[force]) => Call::SetMouseForce(force),
+            ("resize", &[width, height]) => Call::Resize(width, height),
+            _ => return None,
+        })
+    }
+
+    fn apply(self, system: &mut ParticleSystem) {
+        // 只记录成功的调用，重放时不会出错
+        match self {
+            Call::Update => system.update(),
+            Call::UpdateWithDt(dt_ms) => {
+                system.update_with_dt(dt_ms);
+            }
+            Call::UpdateMousePosition(x, y) => system.update_mouse_position(x, y),
+            Call::SetMouseForce(force) => {
+                let _ = system.set_mouse_force(force);
+            }
+            Call::Resize(width, height) => {
+                let _ = system.resize(width, height);
+            }
+        }
+    }
+
+    fn ends_frame(self) -> bool {
+        matches!(self, Call::Update | Call::UpdateWithDt(_))
+    }
+}
+
+/// The state a system was in when recording started, followed by every
+/// `update`, `update_with_dt`, `update_mouse_position`, `set_mouse_force`
+/// and `resize` call made on it. Other changes made while recording,
+/// including `restore` and `restore_json`, are not captured, so a replay
+/// only matches when the session sticks to these calls.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recording {
+    start: Json,
+    calls: Vec<Call>,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Recording {
+    /// Binary file format, with the same versioning and checksum as
+    /// snapshots.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        RECORDING.write_binary(&self.to_json_value())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Recording, ParticleError> {
+        Recording::from_json_value(&RECORDING.read_binary(bytes)?)
+    }
+
+    /// The same recording as a JSON document with one `[name, args...]`
+    /// array per call.
+    pub fn to_json(&self) -> String {
+        RECORDING.write_json(self.to_json_value())
+    }
+
+    pub fn from_json(text: &str) -> Result<Recording, ParticleError> {
+        Recording::from_json_value(&RECORDING.read_json(text)?)
+    }
+
+    pub fn call_count(&self) -> usize {
+        self.calls.len()
+    }
+
+    /// Number of `update` and `update_with_dt` calls.
+    pub fn frame_count(&self) -> usize {
+        self.calls.iter().filter(|call| call.ends_frame()).count()
+    }
+}
+
+impl Recording {
+    fn to_json_value(&self) -> Json {
+        Json::object(vec![
+            ("start", self.start.clone()),
+            (
+                "calls",
+                Json::Array(self.calls.iter().map(|call| call.to_json()).collect()),
+            ),
+        ])
+    }
+
+    fn from_json_value(json: &Json) -> Result<Recording, ParticleError> {
+        let invalid = |message: String| ParticleError::Recording(message);
+        let start = json
+            .get("start")
+            .ok_or_else(|| invalid("missing `start`".to_string()))?;
+        // 提前检查起始状态，错误在读取时而不是重放时报告
+        ParticleSystem::from_snapshot_value(start)?;
+
+        let calls = json
+            .get("calls")
+            .and_then(Json::as_array)
+            .ok_or_else(|| invalid("missing `calls`".to_string()))?
+            .iter()
+            .enumerate()
+            .map(|(i, call)| {
+                Call::from_json(call).ok_or_else(|| {
+                    invalid(format!("calls[{i}]: expected a recorded call, got {call}"))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Recording {
+            start: start.clone(),
+            calls,
+        })
+    }
+}
+
+/// Feeds a recording back into a system restored to the recording's
+/// starting state, seed included, reproducing its frames exactly.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Replayer {
+    system: ParticleSystem,
+    calls: Vec<Call>,
+    next: usize,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Replayer {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(recording: &Recording) -> Result<Replayer, ParticleError> {
+        Ok(Replayer {
+            system: ParticleSystem::from_snapshot_value(&recording.start)?,
+            calls: recording.calls.clone(),
+            next: 0,
+        })
+    }
+
+    /// Applies recorded calls up to and including the next update. Returns
+    /// `false` when no update was left to apply.
+    pub fn next_frame(&mut self) -> bool {
+        while let Some(&call) = self.calls.get(self.next) {
+            self.next += 1;
+            call.apply(&mut self.system);
+            if call.ends_frame() {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Applies every remaining call and returns the number of frames
+    /// replayed.
+    pub fn finish(&mut self) -> usize {
+        let mut frames = 0;
+        while self.next_frame() {
+            frames += 1;
+        }
+        frames
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next == self.calls.len()
+    }
+
+    pub fn into_system(self) -> ParticleSystem {
+        self.system
+    }
+}
+
+impl Replayer {
+    pub fn system(&self) -> &ParticleSystem {
+        &self.system
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ParticleSystem {
+    /// Starts logging calls, beginning from a snapshot of the current
+    /// state. Any recording in progress is discarded.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording {
+            start: self.snapshot_value(),
+            calls: Vec::new(),
+        });
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Ends the recording in progress and returns it.
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recording.take()
+    }
+}
+
+impl ParticleSystem {
+    pub(crate) fn record(&mut self, call: Call) {
+        if let Some(recording) = &mut self.recording {
+            recording.calls.push(call);
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use crate::color::{self, Rgba};
+use crate::connections::{Link, LinkScratch};
+use crate::params;
+use crate::{ParticleError, ParticleSystem};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// 单边像素上限，防止宽高相乘后溢出或一次性分配过大的缓冲区
+const MAX_SIDE: u32 = 16384;
+
+/// Software renderer drawing a system into an RGBA8 image, suitable for
+/// `putImageData` or encoding to an image file.
+///
+/// The system's canvas is scaled to fill the image. Particles are drawn as
+/// anti-aliased discs with their `size` as radius, on top of connection
+/// lines and mouse connections.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+pub struct Renderer {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    background: Rgba,
+    line_width: f64,
+    mouse_connections: bool,
+    links: Vec<Link>,
+    scratch: LinkScratch,
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl Renderer {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new(width: u32, height: u32) -> Result<Renderer, ParticleError> {
+        let mut renderer = Renderer {
+            width: 0,
+            height: 0,
+            pixels: Vec::new(),
+            background: [0.0, 0.0, 0.0, 0.0],
+            line_width: 1.0,
+            mouse_connections: true,
+            links: Vec::new(),
+            scratch: LinkScratch::default(),
+        };
+        renderer.resize(width, height)?;
+        Ok(renderer)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<(), ParticleError> {
+        side("width", width)?;
+        side("height", height)?;
+        self.width = width;
+        self.height = height;
+        self.pixels = vec![0; width as usize * height as usize * 4];
+        Ok(())
+    }
+
+    /// Colour the image is cleared to before each frame, components in
+    /// `0..=1`. Transparent by default.
+    pub fn set_background(&mut self, r: f64, g: f64, b: f64, a: f64) -> Result<(), ParticleError> {
+        self.background = [
+            params::fraction("background r", r)?,
+            params::fraction("background g", g)?,
+            params::fraction("background b", b)?,
+            params::fraction("background a", a)?,
+        ];
+        Ok(())
+    }
+
+    pub fn line_width(&self) -> f64 {
+        self.line_width
+    }
+
+    /// Width of connection lines in image pixels.
+    pub fn set_line_width(&mut self, width: f64) -> Result<(), ParticleError> {
+        self.line_width = params::positive("line_width", width)?;
+        Ok(())
+    }
+
+    pub fn set_draw_mouse_connections(&mut self, enabled: bool) {
+        self.mouse_connections = enabled;
+    }
+
+    /// Draws the system's current state, replacing the previous frame.
+    pub fn render(&mut self, system: &ParticleSystem) {
+        self.clear();
+
+        let scale_x = self.width as f64 / system.width;
+        let scale_y = self.height as f64 / system.height;
+        let scale = (scale_x * scale_y).sqrt();
+        let half_width = self.line_width / 2.0;
+
+        let mut links = std::mem::take(&mut self.links);
+        system.collect_links(&mut self.scratch, &mut links);
+        for link in &links {
+            let p1 = &system.particles[link.a];
+            let p2 = &system.particles[link.b];
+            let [r, g, b, a] = color::blend(system.color_of(p1), system.color_of(p2), 0.5);
+            let alpha = (a * link.opacity).min(1.0);
+            link.segments((p1.x, p1.y), (p2.x, p2.y), |[x1, y1, x2, y2]| {
+                self.line(
+                    (x1 * scale_x, y1 * scale_y),
+                    (x2 * scale_x, y2 * scale_y),
+                    half_width,
+                    [r, g, b],
+                    alpha,
+                );
+            });
+        }
+        self.links = links;
+
+        if self.mouse_connections {
+            let mouse = (system.mouse_x * scale_x, system.mouse_y * scale_y);
+            for &(idx, strength) in &system.mouse_connections {
+                let particle = &system.particles[idx];
+                if !system.mouse_connection_visible(particle) {
+                    continue;
+                }
+                let [r, g, b, a] = system.color_of(particle);
+                let alpha = (a * strength.abs()).min(1.0);
+                let end = (particle.x * scale_x, particle.y * scale_y);
+                self.line(mouse, end, half_width, [r, g, b], alpha);
+            }
+        }
+
+        for particle in &system.particles {
+            let [r, g, b, a] = system.color_of(particle);
+            self.disc(
+                (particle.x * scale_x, particle.y * scale_y),
+                particle.size * scale,
+                [r, g, b],
+                a * particle.fade(),
+            );
+        }
+    }
+
+    pub fn pixels_ptr(&self) -> *const u8 {
+        self.pixels.as_ptr()
+    }
+
+    pub fn pixels_len(&self) -> usize {
+        self.pixels.len()
+    }
+}
+
+impl Renderer {
+    /// The last frame as RGBA8 rows, top to bottom, without premultiplied
+    /// alpha.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    fn clear(&mut self) {
+        let background = self.background.map(|c| (c * 255.0).round() as u8);
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&background);
+        }
+    }
+
+    fn disc(&mut self, (cx, cy): (f64, f64), radius: f64, color: [f64; 3], alpha: f64) {
+        if alpha <= 0.0 || radius <= 0.0 {
+            return;
+        }
+
+        let reach = radius + 0.5;
+        let Some((x0, x1, y0, y1)) = self.clip(cx - reach, cx + reach, cy - reach, cy + reach)
+        else {
+            return;
+        };
+        for y in y0..y1 {
+            for x in x0..x1 {
+                // 以像素中心到圆心的距离估算覆盖率，边缘一个像素内线性过渡
+                let distance = (x as f64 + 0.5 - cx).hypot(y as f64 + 0.5 - cy);
+                let coverage = (reach - distance).clamp(0.0, 1.0) * radius.min(1.0);
+                self.blend(x, y, color, alpha * coverage);
+            }
+        }
+    }
+
+    fn line(
+        &mut self,
+        (ax, ay): (f64, f64),
+        (bx, by): (f64, f64),
+        half_width: f64,
+        color: [f64; 3],
+        alpha: f64,
+    ) {
+        if alpha <= 0.0 {
+            return;
+        }
+
+        let reach = half_width + 0.5;
+        let Some((x0, x1, y0, y1)) = self.clip(
+            ax.min(bx) - reach,
+            ax.max(bx) + reach,
+            ay.min(by) - reach,
+            ay.max(by) + reach,
+        ) else {
+            return;
+        };
+
+        let (dx, dy) = (bx - ax, by - ay);
+        let length_sq = dx * dx + dy * dy;
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let (px, py) = (x as f64 + 0.5 - ax, y as f64 + 0.5 - ay);
+                let t = if length_sq > 0.0 {
+                    ((px * dx + py * dy) / length_sq).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                let distance = (px - dx * t).hypot(py - dy * t);
+                let coverage = (reach - distance).clamp(0.0, 1.0) * half_width.min(0.5) * 2.0;
+                self.blend(x, y, color, alpha * coverage);
+            }
+        }
+    }
+
+    /// Pixel bounds `[x0, x1) × [y0, y1)` covering the given extent, or
+    /// `None` if it lies entirely outside the image.
+    fn clip(&self, left: f64, right: f64, top: f64, bottom: f64) -> Option<(u32, u32, u32, u32)> {
+        let x0 = left.floor().max(0.0);
+        let y0 = top.floor().max(0.0);
+        let x1 = right.ceil().min(self.width as f64);
+        let y1 = bottom.ceil().min(self.height as f64);
+        (x0 < x1 && y0 < y1).then_some((x0 as u32, x1 as u32, y0 as u32, y1 as u32))
+    }
+
+    /// Source-over compositing of a straight-alpha colour.
+    fn blend(&mut self, x: u32, y: u32, color: [f64; 3], alpha: f64) {
+        if alpha <= 0.0 {
+            return;
+        }
+        let index = (y as usize * self.width as usize + x as usize) * 4;
+        let pixel = &mut self.pixels[index..index + 4];
+
+        let alpha = alpha.min(1.0);
+        let dst_alpha = f64::from(pixel[3]) / 255.0;
+        let out_alpha = alpha + dst_alpha * (1.0 - alpha);
+        for (channel, source) in pixel.iter_mut().zip(color) {
+            let dst = f64::from(*channel) / 255.0;
+            let value = (source * alpha + dst * dst_alpha * (1.0 - alpha)) / out_alpha;
+            *channel = (value * 255.0).round() as u8;
+        }
+        pixel[3] = (out_alpha * 255.0).round() as u8;
+    }
+}
+
+fn side(name: &'static str, value: u32) -> Result<(), ParticleError> {
+    if (1..=MAX_SIDE).contains(&value) {
+        Ok(())
+    } else {
+        Err(ParticleError::InvalidValue {
+            name,
+            value: f64::from(value),
+            expected: "between 1 and 16384 pixels",
+        })
+    }
+}
@@ -0,0 +1,77 @@
+use crate::color;
+use rand::{Error, RngCore, SeedableRng};
+
+/// xoshiro256++, the generator behind `rand`'s `SmallRng` on 64-bit
+/// targets, with its state exposed so snapshots can save and restore it.
+/// Seeded the same way it produces the same numbers as `SmallRng` there,
+/// and the same numbers on every target.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Xoshiro256 {
+    s: [u64; 4],
+}
+
+impl Xoshiro256 {
+    pub fn state(&self) -> [u64; 4] {
+        self.s
+    }
+
+    /// `None` for the all-zero state, which the generator never leaves.
+    pub fn from_state(s: [u64; 4]) -> Option<Xoshiro256> {
+        (s != [0; 4]).then_some(Xoshiro256 { s })
+    }
+}
+
+impl SeedableRng for Xoshiro256 {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: [u8; 32]) -> Xoshiro256 {
+        let mut s = [0; 4];
+        for (word, chunk) in s.iter_mut().zip(seed.chunks_exact(8)) {
+            *word = u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+        // 与 rand 一致：全零种子改用 SplitMix64 生成的状态
+        if s == [0; 4] {
+            let phi = 0x9e37_79b9_7f4a_7c15u64;
+            for (i, word) in s.iter_mut().enumerate() {
+                *word = color::mix(phi.wrapping_mul(i as u64));
+            }
+        }
+        Xoshiro256 { s }
+    }
+}
+
+impl RngCore for Xoshiro256 {
+    fn next_u32(&mut self) -> u32 {
+        // 低位有线性相关，取高 32 位
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let result = self.s[0]
+            .wrapping_add(self.s[3])
+            .rotate_left(23)
+            .wrapping_add(self.s[0]);
+
+        let t = self.s[1] << 17;
+        self.s[2] ^= self.s[0];
+        self.s[3] ^= self.s[1];
+        self.s[1] ^= self.s[2];
+        self.s[0] ^= self.s[3];
+        self.s[2] ^= t;
+        self.s[3] = self.s[3].rotate_left(45);
+
+        result
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
@@ -0,0 +1,865 @@
+//! Versioned snapshots of a whole system. Both encodings carry the same
+//! document: a JSON-shaped tree of the system's config, particles, id
+//! table, attractors, emitters, RNG state and timing. The binary form
+//! stores that tree with a tagged encoding behind a header and a CRC-32.
+//!
+//! Readers ignore object keys and trailing record entries they do not know,
+//! so later versions can add state without breaking older readers. A
+//! snapshot records both the version that wrote it and the oldest version
+//! able to read it, which is raised only when older readers would resume
+//! incorrectly.
+
+use crate::attractors::Attractor;
+use crate::config::ParticleConfig;
+use crate::emitters::Emitter;
+use crate::ids::{ParticleIds, MAX_PARTICLES};
+use crate::json::Json;
+use crate::looping::Looping;
+use crate::rng::Xoshiro256;
+use crate::{
+    AttractorKind, ConnectionFormat, EmitterShape, Falloff, Particle, ParticleError,
+    ParticleSystem, Range,
+};
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Format version written by this build, and the newest it can read.
+const VERSION: u32 = 1;
+/// Oldest reader version able to resume from what this build writes.
+const COMPATIBLE: u32 = 1;
+// 魔数、版本、兼容版本、正文长度
+const HEADER_LEN: usize = 4 + 2 + 2 + 4;
+const MAX_DEPTH: usize = 32;
+
+const PARTICLE_FIELDS: usize = 22;
+const ATTRACTOR_FIELDS: usize = 8;
+const EMITTER_FIELDS: usize = 19;
+
+// 二进制树的值类型标记
+const NULL: u8 = 0;
+const FALSE: u8 = 1;
+const TRUE: u8 = 2;
+const NUMBER: u8 = 3;
+const INTEGER: u8 = 4;
+const STRING: u8 = 5;
+const ARRAY: u8 = 6;
+const OBJECT: u8 = 7;
+
+/// A kind of versioned document: how it is marked in binary and JSON form
+/// and which error its problems are reported as.
+pub(crate) struct Container {
+    magic: &'static [u8; 4],
+    format: &'static str,
+    name: &'static str,
+    error: fn(String) -> ParticleError,
+}
+
+const SNAPSHOT: Container = Container {
+    magic: b"FPSN",
+    format: "floating-particles-snapshot",
+    name: "snapshot",
+    error: ParticleError::Snapshot,
+};
+
+impl Container {
+    pub const fn new(
+        magic: &'static [u8; 4],
+        format: &'static str,
+        name: &'static str,
+        error: fn(String) -> ParticleError,
+    ) -> Container {
+        Container {
+            magic,
+            format,
+            name,
+            error,
+        }
+    }
+
+    /// Header, tagged body and CRC-32 of everything before it.
+    pub fn write_binary(&self, body: &Json) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        encode(body, &mut encoded);
+
+        let mut blob = Vec::with_capacity(HEADER_LEN + encoded.len() + 4);
+        blob.extend_from_slice(self.magic);
+        blob.extend_from_slice(&(VERSION as u16).to_le_bytes());
+        blob.extend_from_slice(&(COMPATIBLE as u16).to_le_bytes());
+        blob.extend_from_slice(&(encoded.len() as u32).to_le_bytes());
+        blob.extend_from_slice(&encoded);
+        let checksum = crc32(&blob);
+        blob.extend_from_slice(&checksum.to_le_bytes());
+        blob
+    }
+
+    /// The body's fields after `format`, `version` and `compatible` keys.
+    pub fn write_json(&self, body: Json) -> String {
+        let Json::Object(body) = body else {
+            unreachable!("container bodies are objects");
+        };
+        let mut fields = vec![
+            ("format".to_string(), self.format.into()),
+            ("version".to_string(), integer(u64::from(VERSION))),
+            ("compatible".to_string(), integer(u64::from(COMPATIBLE))),
+        ];
+        fields.extend(body);
+        Json::Object(fields).to_pretty_string()
+    }
+
+    pub fn read_binary(&self, blob: &[u8]) -> Result<Json, ParticleError> {
+        if !blob.starts_with(self.magic) {
+            return Err(self.error(&format!("not a particle system {}", self.name)));
+        }
+        if blob.len() < HEADER_LEN + 4 {
+            return Err(self.error(&format!("{} is truncated", self.name)));
+        }
+        let version = u32::from(u16::from_le_bytes([blob[4], blob[5]]));
+        let compatible = u32::from(u16::from_le_bytes([blob[6], blob[7]]));
+        self.check_version(version, compatible)?;
+
+        let length = u32::from_le_bytes(blob[8..12].try_into().unwrap()) as usize;
+        let Some(end) = HEADER_LEN
+            .checked_add(length)
+            .filter(|&end| end + 4 <= blob.len())
+        else {
+            return Err(self.error(&format!("{} is truncated", self.name)));
+        };
+        let stored = u32::from_le_bytes(blob[end..end + 4].try_into().unwrap());
+        if crc32(&blob[..end]) != stored {
+            return Err(self.error("checksum mismatch"));
+        }
+
+        let mut reader = Reader {
+            bytes: &blob[HEADER_LEN..end],
+            pos: 0,
+            container: self,
+        };
+        let body = reader.value(0)?;
+        if reader.pos != reader.bytes.len() {
+            return Err(self.error(&format!("trailing bytes after the {} body", self.name)));
+        }
+        Ok(body)
+    }
+
+    pub fn read_json(&self, text: &str) -> Result<Json, ParticleError> {
+        let json = Json::parse(text).map_err(|error| ParticleError::Json {
+            line: error.line,
+            column: error.column,
+            message: error.message,
+        })?;
+        if json.get("format").and_then(Json::as_str) != Some(self.format) {
+            return Err(self.error(&format!("not a particle system {}", self.name)));
+        }
+        let version = |key| {
+            json.get(key)
+                .and_then(Json::as_f64)
+                .filter(|version| {
+                    version.fract() == 0.0 && (1.0..=f64::from(u16::MAX)).contains(version)
+                })
+                .map(|version| version as u32)
+                .ok_or_else(|| self.error("invalid format version"))
+        };
+        self.check_version(version("version")?, version("compatible")?)?;
+        Ok(json)
+    }
+
+    fn check_version(&self, version: u32, compatible: u32) -> Result<(), ParticleError> {
+        if version == 0 || compatible == 0 || compatible > version {
+            return Err(self.error("invalid format version"));
+        }
+        if compatible > VERSION {
+            return Err(self.error(&format!(
+                "written by format version {version}, which needs a reader for version \
+                 {compatible} or later; this build reads up to version {VERSION}"
+            )));
+        }
+        Ok(())
+    }
+
+    fn error(&self, message: &str) -> ParticleError {
+        (self.error)(message.to_string())
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ParticleSystem {
+    /// Compact binary snapshot of the complete simulation state, from which
+    /// `restore` resumes exactly where this system is now.
+    pub fn snapshot(&self) -> Vec<u8> {
+        SNAPSHOT.write_binary(&self.snapshot_value())
+    }
+
+    /// The same snapshot as a JSON document, for inspection and diffing.
+    /// Non-finite numbers are written as the strings `"nan"`, `"inf"` and
+    /// `"-inf"`.
+    pub fn snapshot_json(&self) -> String {
+        SNAPSHOT.write_json(self.snapshot_value())
+    }
+
+    /// Replaces this system with the one saved in a binary snapshot. On
+    /// error the system is left unchanged. A recording in progress keeps
+    /// running, but the restore itself is not captured.
+    pub fn restore(&mut self, blob: &[u8]) -> Result<(), ParticleError> {
+        let body = SNAPSHOT.read_binary(blob)?;
+        self.replace(ParticleSystem::from_snapshot_value(&body)?);
+        Ok(())
+    }
+
+    /// Like `restore`, from the output of `snapshot_json`.
+    pub fn restore_json(&mut self, text: &str) -> Result<(), ParticleError> {
+        let body = SNAPSHOT.read_json(text)?;
+        self.replace(ParticleSystem::from_snapshot_value(&body)?);
+        Ok(())
+    }
+}
+
+impl ParticleSystem {
+    fn replace(&mut self, restored: ParticleSystem) {
+        // 录制不属于快照，恢复后继续录制
+        let recording = self.recording.take();
+        *self = ParticleSystem {
+            recording,
+            ..restored
+        };
+    }
+
+    pub(crate) fn snapshot_value(&self) -> Json {
+        let (slots, free) = self.ids.parts();
+        let slots = slots
+            .into_iter()
+            .map(|(generation, index)| {
+                Json::Array(vec![
+                    integer(u64::from(generation)),
+                    index.map_or(Json::Null, |index| integer(u64::from(index))),
+                ])
+            })
+            .collect();
+        let free = free.iter().map(|&slot| integer(u64::from(slot))).collect();
+
+        let looping = match self.looping {
+            Some(Looping {
+                step,
+                anchor: Some((steps, width, height)),
+                ..
+            }) => Json::object(vec![
+                ("step", integer(step)),
+                (
+                    "anchor",
+                    Json::Array(vec![integer(steps), float(width), float(height)]),
+                ),
+            ]),
+            _ => Json::Null,
+        };
+
+        Json::object(vec![
+            ("config", self.config().to_json_value()),
+            (
+                "rng",
+                Json::Array(
+                    self.rng
+                        .state()
+                        .iter()
+                        .map(|word| Json::String(format!("{word:016x}")))
+                        .collect(),
+                ),
+            ),
+            ("accumulator_ms", float(self.accumulator_ms)),
+            ("alpha", float(self.alpha)),
+            (
+                "mouse",
+                Json::Array(vec![float(self.mouse_x), float(self.mouse_y)]),
+            ),
+            (
+                "mouse_connections",
+                Json::Array(
+                    self.mouse_connections
+                        .iter()
+                        .map(|&(index, strength)| {
+                            Json::Array(vec![integer(index as u64), float(strength)])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "output",
+                Json::object(vec![
+                    ("connection_format", integer(self.connection_format as u64)),
+                    ("particle_fields", integer(u64::from(self.particle_fields))),
+                    ("webgl", self.webgl_output.into()),
+                ]),
+            ),
+            ("loop", looping),
+            (
+                "ids",
+                Json::object(vec![
+                    ("slots", Json::Array(slots)),
+                    ("free", Json::Array(free)),
+                ]),
+            ),
+            (
+                "particles",
+                Json::Array(self.particles.iter().map(particle_record).collect()),
+            ),
+            (
+                "attractors",
+                Json::Array(self.attractors.iter().map(attractor_record).collect()),
+            ),
+            (
+                "next_attractor_id",
+                integer(u64::from(self.next_attractor_id)),
+            ),
+            (
+                "emitters",
+                Json::Array(self.emitters.iter().map(emitter_record).collect()),
+            ),
+            ("next_emitter_id", integer(u64::from(self.next_emitter_id))),
+        ])
+    }
+
+    pub(crate) fn from_snapshot_value(json: &Json) -> Result<ParticleSystem, ParticleError> {
+        let mut config = ParticleConfig::from_json_value(field(json, "", "config")?)
+            .and_then(|config| config.validate().map(|()| config))
+            .map_err(|error| corrupt(&format!("config: {error}")))?;
+        let Some(seed) = config.seed() else {
+            return Err(corrupt("config: missing seed"));
+        };
+        config.particle_count = 0;
+        let mut system = ParticleSystem::build(&config, seed);
+
+        let words = records(json, "rng", 4)?;
+        let mut state = [0; 4];
+        for (word, value) in state.iter_mut().zip(words) {
+            *word = value
+                .as_str()
+                .filter(|text| text.len() == 16)
+                .and_then(|text| u64::from_str_radix(text, 16).ok())
+                .ok_or_else(|| corrupt("rng: expected four 16-digit hex strings"))?;
+        }
+        system.rng =
+            Xoshiro256::from_state(state).ok_or_else(|| corrupt("rng: state is all zero"))?;
+
+        system.accumulator_ms = number(field(json, "", "accumulator_ms")?, "accumulator_ms")?;
+        system.alpha = number(field(json, "", "alpha")?, "alpha")?;
+        let mouse = records(json, "mouse", 2)?;
+        system.mouse_x = number(&mouse[0], "mouse")?;
+        system.mouse_y = number(&mouse[1], "mouse")?;
+
+        let output = field(json, "", "output")?;
+        system.connection_format = match unsigned(
+            field(output, "output", "connection_format")?,
+            "output.connection_format",
+        )? {
+            0 => ConnectionFormat::Segments,
+            1 => ConnectionFormat::Indexed,
+            _ => return Err(corrupt("output.connection_format: unknown format")),
+        };
+        system.set_particle_fields(unsigned(
+            field(output, "output", "particle_fields")?,
+            "output.particle_fields",
+        )?);
+        system.webgl_output = boolean(field(output, "output", "webgl")?, "output.webgl")?;
+
+        let particles = array(field(json, "", "particles")?, "particles")?;
+        if particles.len() > MAX_PARTICLES {
+            return Err(ParticleError::TooManyParticles);
+        }
+        system.particles = particles
+            .iter()
+            .map(read_particle)
+            .collect::<Result<_, _>>()?;
+
+        let ids = field(json, "", "ids")?;
+        let slots = array(field(ids, "ids", "slots")?, "ids.slots")?
+            .iter()
+            .map(|slot| match slot.as_array() {
+                Some([generation, index, ..]) => Ok((
+                    unsigned(generation, "ids.slots")?,
+                    match index {
+                        Json::Null => None,
+                        index => Some(unsigned(index, "ids.slots")?),
+                    },
+                )),
+                _ => Err(corrupt("ids.slots: expected [generation, index] pairs")),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let free = array(field(ids, "ids", "free")?, "ids.free")?
+            .iter()
+            .map(|slot| unsigned(slot, "ids.free"))
+            .collect::<Result<_, _>>()?;
+        system.ids = ParticleIds::from_parts(&slots, free, &system.particles)
+            .ok_or_else(|| corrupt("ids: table does not match the particles"))?;
+
+        system.mouse_connections =
+            array(field(json, "", "mouse_connections")?, "mouse_connections")?
+                .iter()
+                .map(|connection| match connection.as_array() {
+                    Some([index, strength, ..]) => {
+                        let index: usize = unsigned(index, "mouse_connections")?;
+                        if index >= system.particles.len() {
+                            return Err(corrupt("mouse_connections: particle index out of range"));
+                        }
+                        Ok((index, number(strength, "mouse_connections")?))
+                    }
+                    _ => Err(corrupt(
+                        "mouse_connections: expected [index, strength] pairs",
+                    )),
+                })
+                .collect::<Result<_, _>>()?;
+
+        if let Some(looping) = system.looping.as_mut() {
+            if let loop_state @ Json::Object(_) = field(json, "", "loop")? {
+                let step = unsigned(field(loop_state, "loop", "step")?, "loop.step")?;
+                let anchor = records(loop_state, "anchor", 3)?;
+                let steps = unsigned(&anchor[0], "loop.anchor")?;
+                if step >= steps {
+                    return Err(corrupt("loop.step: beyond the end of the loop"));
+                }
+                looping.step = step;
+                looping.anchor = Some((
+                    steps,
+                    number(&anchor[1], "loop.anchor")?,
+                    number(&anchor[2], "loop.anchor")?,
+                ));
+            }
+        }
+
+        system.attractors = array(field(json, "", "attractors")?, "attractors")?
+            .iter()
+            .map(read_attractor)
+            .collect::<Result<_, _>>()?;
+        system.next_attractor_id =
+            unsigned(field(json, "", "next_attractor_id")?, "next_attractor_id")?;
+        system.emitters = array(field(json, "", "emitters")?, "emitters")?
+            .iter()
+            .map(read_emitter)
+            .collect::<Result<_, _>>()?;
+        system.next_emitter_id = unsigned(field(json, "", "next_emitter_id")?, "next_emitter_id")?;
+
+        system.rebuild_grid();
+        Ok(system)
+    }
+}
+
+fn particle_record(particle: &Particle) -> Json {
+    let (origin_x, origin_y) = particle.loop_origin;
+    Json::Array(vec![
+        integer(u64::from(particle.id)),
+        float(particle.x),
+        float(particle.y),
+        float(particle.prev_x),
+        float(particle.prev_y),
+        float(particle.vx),
+        float(particle.vy),
+        float(particle.size),
+        float(particle.target_size),
+        float(particle.base_vx),
+        float(particle.base_vy),
+        float(particle.orbit_angle),
+        float(particle.orbit_speed),
+        float(particle.orbit_radius),
+        particle.is_orbiting.into(),
+        float(particle.orbit_blend),
+        float(particle.age_ms),
+        float(particle.lifetime_ms),
+        float(particle.fade_in_ms),
+        float(particle.fade_out_ms),
+        float(origin_x),
+        float(origin_y),
+        float(particle.target_vx),
+        float(particle.target_vy),
+        float(particle.drift.0),
+        float(particle.drift.1),
+    ])
+}
+
+fn read_particle(record: &Json) -> Result<Particle, ParticleError> {
+    let values = record_values(record, "particles", PARTICLE_FIELDS)?;
+    let n = |i: usize| number(&values[i], "particles");
+    // 目标速度和原始采样值是后加的字段，缺省时取基础速度
+    let all = array(record, "particles")?;
+    let or_base = |i: usize, base: usize| match all.get(i) {
+        Some(value) => number(value, "particles"),
+        None => n(base),
+    };
+    Ok(Particle {
+        id: unsigned(&values[0], "particles")?,
+        x: n(1)?,
+        y: n(2)?,
+        prev_x: n(3)?,
+        prev_y: n(4)?,
+        vx: n(5)?,
+        vy: n(6)?,
+        size: n(7)?,
+        target_size: n(8)?,
+        base_vx: n(9)?,
+        base_vy: n(10)?,
+        target_vx: or_base(22, 9)?,
+        target_vy: or_base(23, 10)?,
+        orbit_angle: n(11)?,
+        orbit_speed: n(12)?,
+        orbit_radius: n(13)?,
+        is_orbiting: boolean(&values[14], "particles")?,
+        orbit_blend: n(15)?,
+        age_ms: n(16)?,
+        lifetime_ms: n(17)?,
+        fade_in_ms: n(18)?,
+        fade_out_ms: n(19)?,
+        loop_origin: (n(20)?, n(21)?),
+        drift: (or_base(24, 9)?, or_base(25, 10)?),
+    })
+}
+
+fn attractor_record(attractor: &Attractor) -> Json {
+    Json::Array(vec![
+        integer(u64::from(attractor.id)),
+        float(attractor.x),
+        float(attractor.y),
+        float(attractor.radius),
+        float(attractor.strength),
+        integer(attractor.kind as u64),
+        integer(attractor.falloff as u64),
+        attractor.remaining_ms.map_or(Json::Null, float),
+    ])
+}
+
+fn read_attractor(record: &Json) -> Result<Attractor, ParticleError> {
+    let values = record_values(record, "attractors", ATTRACTOR_FIELDS)?;
+    let n = |i: usize| number(&values[i], "attractors");
+    let attractor = Attractor {
+        id: unsigned(&values[0], "attractors")?,
+        x: n(1)?,
+        y: n(2)?,
+        radius: n(3)?,
+        strength: n(4)?,
+        kind: match unsigned(&values[5], "attractors")? {
+            0 => AttractorKind::Attract,
+            1 => AttractorKind::Repel,
+            2 => AttractorKind::Vortex,
+            _ => return Err(corrupt("attractors: unknown kind")),
+        },
+        falloff: match unsigned(&values[6], "attractors")? {
+            0 => Falloff::Constant,
+            1 => Falloff::Linear,
+            2 => Falloff::Quadratic,
+            3 => Falloff::Smooth,
+            _ => return Err(corrupt("attractors: unknown falloff")),
+        },
+        remaining_ms: match &values[7] {
+            Json::Null => None,
+            value => Some(number(value, "attractors")?),
+        },
+    };
+    attractor
+        .validate()
+        .map_err(|error| corrupt(&format!("attractors: {error}")))?;
+    Ok(attractor)
+}
+
+fn emitter_record(emitter: &Emitter) -> Json {
+    Json::Array(vec![
+        integer(u64::from(emitter.id)),
+        integer(emitter.shape as u64),
+        float(emitter.x1),
+        float(emitter.y1),
+        float(emitter.x2),
+        float(emitter.y2),
+        float(emitter.rate),
+        integer(u64::from(emitter.burst_count)),
+        integer(u64::from(emitter.pending_bursts)),
+        float(emitter.direction),
+        float(emitter.spread),
+        float(emitter.speed.min),
+        float(emitter.speed.max),
+        float(emitter.lifetime_ms),
+        float(emitter.fade_in_ms),
+        float(emitter.fade_out_ms),
+        emitter.enabled.into(),
+        float(emitter.accumulator),
+        emitter
+            .last_mouse
+            .map_or(Json::Null, |(x, y)| Json::Array(vec![float(x), float(y)])),
+    ])
+}
+
+fn read_emitter(record: &Json) -> Result<Emitter, ParticleError> {
+    let values = record_values(record, "emitters", EMITTER_FIELDS)?;
+    let n = |i: usize| number(&values[i], "emitters");
+    let emitter = Emitter {
+        id: unsigned(&values[0], "emitters")?,
+        shape: match unsigned(&values[1], "emitters")? {
+            0 => EmitterShape::Point,
+            1 => EmitterShape::Line,
+            2 => EmitterShape::Rectangle,
+            3 => EmitterShape::MouseTrail,
+            _ => return Err(corrupt("emitters: unknown shape")),
+        },
+        x1: n(2)?,
+        y1: n(3)?,
+        x2: n(4)?,
+        y2: n(5)?,
+        rate: n(6)?,
+        burst_count: unsigned(&values[7], "emitters")?,
+        pending_bursts: unsigned(&values[8], "emitters")?,
+        direction: n(9)?,
+        spread: n(10)?,
+        speed: Range::new(n(11)?, n(12)?),
+        lifetime_ms: n(13)?,
+        fade_in_ms: n(14)?,
+        fade_out_ms: n(15)?,
+        enabled: boolean(&values[16], "emitters")?,
+        accumulator: n(17)?,
+        last_mouse: match &values[18] {
+            Json::Null => None,
+            value => match value.as_array() {
+                Some([x, y]) => Some((number(x, "emitters")?, number(y, "emitters")?)),
+                _ => return Err(corrupt("emitters: expected [x, y] or null")),
+            },
+        },
+    };
+    emitter
+        .validate()
+        .map_err(|error| corrupt(&format!("emitters: {error}")))?;
+    Ok(emitter)
+}
+
+fn corrupt(message: &str) -> ParticleError {
+    ParticleError::Snapshot(message.to_string())
+}
+
+// JSON 无法表示 NaN 和无穷大，改写为字符串
+pub(crate) fn float(value: f64) -> Json {
+    if value.is_nan() {
+        "nan".into()
+    } else if value.is_infinite() {
+        if value > 0.0 { "inf" } else { "-inf" }.into()
+    } else {
+        value.into()
+    }
+}
+
+fn integer(value: u64) -> Json {
+    Json::Number(value as f64)
+}
+
+fn field<'a>(json: &'a Json, parent: &str, key: &str) -> Result<&'a Json, ParticleError> {
+    json.get(key).ok_or_else(|| {
+        if parent.is_empty() {
+            corrupt(&format!("missing `{key}`"))
+        } else {
+            corrupt(&format!("{parent}: missing `{key}`"))
+        }
+    })
+}
+
+fn array<'a>(value: &'a Json, path: &str) -> Result<&'a [Json], ParticleError> {
+    value
+        .as_array()
+        .ok_or_else(|| corrupt(&format!("{path}: expected an array")))
+}
+
+/// An array field with at least `len` entries.
+fn records<'a>(json: &'a Json, key: &str, len: usize) -> Result<&'a [Json], ParticleError> {
+    record_values(field(json, "", key)?, key, len)
+}
+
+fn record_values<'a>(
+    record: &'a Json,
+    path: &str,
+    len: usize,
+) -> Result<&'a [Json], ParticleError> {
+    // 新版本可能在记录末尾追加字段，多出的部分忽略
+    array(record, path)?
+        .get(..len)
+        .ok_or_else(|| corrupt(&format!("{path}: expected at least {len} values")))
+}
+
+/// Inverse of `float`.
+pub(crate) fn float_value(value: &Json) -> Option<f64> {
+    match value {
+        Json::Number(value) => Some(*value),
+        Json::String(text) if text == "nan" => Some(f64::NAN),
+        Json::String(text) if text == "inf" => Some(f64::INFINITY),
+        Json::String(text) if text == "-inf" => Some(f64::NEG_INFINITY),
+        _ => None,
+    }
+}
+
+fn number(value: &Json, path: &str) -> Result<f64, ParticleError> {
+    float_value(value).ok_or_else(|| corrupt(&format!("{path}: expected a number")))
+}
+
+fn unsigned<T: TryFrom<u64>>(value: &Json, path: &str) -> Result<T, ParticleError> {
+    value
+        .as_f64()
+        .filter(|number| number.fract() == 0.0 && *number >= 0.0 && *number < 2f64.powi(53))
+        .and_then(|number| T::try_from(number as u64).ok())
+        .ok_or_else(|| corrupt(&format!("{path}: expected a whole number in range")))
+}
+
+fn boolean(value: &Json, path: &str) -> Result<bool, ParticleError> {
+    value
+        .as_bool()
+        .ok_or_else(|| corrupt(&format!("{path}: expected a boolean")))
+}
+
+/// Tagged binary form of a JSON tree. Whole numbers are stored as LEB128
+/// varints and all other numbers as little-endian doubles, so values
+/// survive bit for bit.
+fn encode(value: &Json, out: &mut Vec<u8>) {
+    match value {
+        Json::Null => out.push(NULL),
+        Json::Bool(false) => out.push(FALSE),
+        Json::Bool(true) => out.push(TRUE),
+        Json::Number(number)
+            if number.fract() == 0.0
+                && *number >= 0.0
+                && *number < 2f64.powi(53)
+                && number.is_sign_positive() =>
+        {
+            out.push(INTEGER);
+            varint(*number as u64, out);
+        }
+        Json::Number(number) => {
+            out.push(NUMBER);
+            out.extend_from_slice(&number.to_le_bytes());
+        }
+        Json::String(text) => {
+            out.push(STRING);
+            string(text, out);
+        }
+        Json::Array(values) => {
+            out.push(ARRAY);
+            varint(values.len() as u64, out);
+            for value in values {
+                encode(value, out);
+            }
+        }
+        Json::Object(fields) => {
+            out.push(OBJECT);
+            varint(fields.len() as u64, out);
+            for (key, value) in fields {
+                string(key, out);
+                encode(value, out);
+            }
+        }
+    }
+}
+
+fn varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn string(text: &str, out: &mut Vec<u8>) {
+    varint(text.len() as u64, out);
+    out.extend_from_slice(text.as_bytes());
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    container: &'a Container,
+}
+
+impl Reader<'_> {
+    fn value(&mut self, depth: usize) -> Result<Json, ParticleError> {
+        if depth > MAX_DEPTH {
+            return Err(self.container.error("values are nested too deeply"));
+        }
+        let tag = self.take(1)?[0];
+        Ok(match tag {
+            NULL => Json::Null,
+            FALSE => Json::Bool(false),
+            TRUE => Json::Bool(true),
+            NUMBER => Json::Number(f64::from_le_bytes(self.take(8)?.try_into().unwrap())),
+            INTEGER => {
+                let value = self.varint()?;
+                if value >= 1 << 53 {
+                    return Err(self.container.error("integer out of range"));
+                }
+                Json::Number(value as f64)
+            }
+            STRING => Json::String(self.string()?),
+            ARRAY => {
+                let count = self.count()?;
+                let mut values = Vec::with_capacity(count);
+                for _ in 0..count {
+                    values.push(self.value(depth + 1)?);
+                }
+                Json::Array(values)
+            }
+            OBJECT => {
+                let count = self.count()?;
+                let mut fields = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let key = self.string()?;
+                    fields.push((key, self.value(depth + 1)?));
+                }
+                Json::Object(fields)
+            }
+            _ => return Err(self.container.error(&format!("unknown value tag {tag}"))),
+        })
+    }
+
+    fn take(&mut self, len: usize) -> Result<&[u8], ParticleError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| {
+                self.container
+                    .error(&format!("{} body ends early", self.container.name))
+            })?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn varint(&mut self) -> Result<u64, ParticleError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(self.container.error("varint is too long"))
+    }
+
+    /// An element count, which cannot exceed the bytes left since every
+    /// element takes at least one.
+    fn count(&mut self) -> Result<usize, ParticleError> {
+        let count = self.varint()?;
+        if count > (self.bytes.len() - self.pos) as u64 {
+            return Err(self
+                .container
+                .error(&format!("{} body ends early", self.container.name)));
+        }
+        Ok(count as usize)
+    }
+
+    fn string(&mut self) -> Result<String, ParticleError> {
+        let len = self.count()?;
+        String::from_utf8(self.take(len)?.to_vec())
+            .map_err(|_| self.container.error("string is not UTF-8"))
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,308 @@
+use crate::color::{self, Rgba};
+use crate::connections::LinkScratch;
+use crate::params;
+use crate::{ParticleError, ParticleSystem};
+use std::fmt::Write;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// How `to_svg` arranges its elements.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SvgGrouping {
+    /// Every element carries all of its attributes.
+    Flat = 0,
+    /// Connections, mouse connections and particles each sit in a `<g>`
+    /// with the id `connections`, `mouse-connections` or `particles`, which
+    /// holds their shared attributes and can be targeted from CSS.
+    Layers = 1,
+}
+
+/// Options for `ParticleSystem::to_svg`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvgOptions {
+    pub(crate) view_box: Option<[f64; 4]>,
+    pub(crate) stroke: Option<Rgba>,
+    pub(crate) stroke_width: f64,
+    pub(crate) background: Option<Rgba>,
+    pub(crate) grouping: SvgGrouping,
+    pub(crate) mouse_connections: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> SvgOptions {
+        SvgOptions {
+            view_box: None,
+            stroke: None,
+            stroke_width: 1.0,
+            background: None,
+            grouping: SvgGrouping::Layers,
+            mouse_connections: true,
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl SvgOptions {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> SvgOptions {
+        SvgOptions::default()
+    }
+
+    /// Region of the canvas shown by the document. Defaults to the whole
+    /// canvas.
+    pub fn set_view_box(
+        &mut self,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    ) -> Result<(), ParticleError> {
+        self.view_box = Some([
+            params::finite("view_box x", x)?,
+            params::finite("view_box y", y)?,
+            params::positive("view_box width", width)?,
+            params::positive("view_box height", height)?,
+        ]);
+        Ok(())
+    }
+
+    pub fn clear_view_box(&mut self) {
+        self.view_box = None;
+    }
+
+    /// Single colour for connection lines, components in `0..=1`, instead
+    /// of the blend of their endpoints' colours.
+    pub fn set_stroke(&mut self, r: f64, g: f64, b: f64, a: f64) -> Result<(), ParticleError> {
+        self.stroke = Some(rgba("stroke", r, g, b, a)?);
+        Ok(())
+    }
+
+    pub fn clear_stroke(&mut self) {
+        self.stroke = None;
+    }
+
+    pub fn stroke_width(&self) -> f64 {
+        self.stroke_width
+    }
+
+    pub fn set_stroke_width(&mut self, width: f64) -> Result<(), ParticleError> {
+        self.stroke_width = params::positive("stroke_width", width)?;
+        Ok(())
+    }
+
+    /// Fills the view box with a colour behind everything else. Transparent
+    /// by default.
+    pub fn set_background(&mut self, r: f64, g: f64, b: f64, a: f64) -> Result<(), ParticleError> {
+        self.background = Some(rgba("background", r, g, b, a)?);
+        Ok(())
+    }
+
+    pub fn clear_background(&mut self) {
+        self.background = None;
+    }
+
+    pub fn grouping(&self) -> SvgGrouping {
+        self.grouping
+    }
+
+    pub fn set_grouping(&mut self, grouping: SvgGrouping) {
+        self.grouping = grouping;
+    }
+
+    pub fn set_mouse_connections(&mut self, enabled: bool) {
+        self.mouse_connections = enabled;
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ParticleSystem {
+    /// The current frame as a standalone SVG document: connection lines and
+    /// mouse connections with their computed opacity, under circles for the
+    /// particles.
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        let layers = options.grouping == SvgGrouping::Layers;
+        let mut svg = String::new();
+        begin(&mut svg, options, self.width, self.height);
+
+        // 分层时公共属性写在 <g> 上，单个元素只保留各自不同的部分
+        let line_style = line_style(options);
+        let (open, inline) = if layers {
+            (line_style.as_str(), "")
+        } else {
+            ("", line_style.as_str())
+        };
+
+        let mut scratch = LinkScratch::default();
+        let mut links = Vec::new();
+        self.collect_links(&mut scratch, &mut links);
+        if layers {
+            let _ = writeln!(svg, r#"<g id="connections"{open}>"#);
+        }
+        for link in &links {
+            let p1 = &self.particles[link.a];
+            let p2 = &self.particles[link.b];
+            let [r, g, b, a] = options
+                .stroke
+                .unwrap_or_else(|| color::blend(self.color_of(p1), self.color_of(p2), 0.5));
+            let color = if options.stroke.is_some() {
+                String::new()
+            } else {
+                format!(r#" stroke="{}""#, hex([r, g, b]))
+            };
+            let opacity = (a * link.opacity).min(1.0);
+            link.segments((p1.x, p1.y), (p2.x, p2.y), |[x1, y1, x2, y2]| {
+                line(&mut svg, [x1, y1, x2, y2], &color, opacity, inline);
+            });
+        }
+        if layers {
+            svg.push_str("</g>\n");
+        }
+
+        if options.mouse_connections {
+            if layers {
+                let _ = writeln!(svg, r#"<g id="mouse-connections"{open}>"#);
+            }
+            for &(idx, strength) in &self.mouse_connections {
+                let particle = &self.particles[idx];
+                if !self.mouse_connection_visible(particle) {
+                    continue;
+                }
+                let [r, g, b, a] = options.stroke.unwrap_or_else(|| self.color_of(particle));
+                let color = if options.stroke.is_some() {
+                    String::new()
+                } else {
+                    format!(r#" stroke="{}""#, hex([r, g, b]))
+                };
+                let opacity = (a * strength.abs()).min(1.0);
+                line(
+                    &mut svg,
+                    [self.mouse_x, self.mouse_y, particle.x, particle.y],
+                    &color,
+                    opacity,
+                    inline,
+                );
+            }
+            if layers {
+                svg.push_str("</g>\n");
+            }
+        }
+
+        if layers {
+            svg.push_str("<g id=\"particles\">\n");
+        }
+        for particle in &self.particles {
+            let [r, g, b, a] = self.color_of(particle);
+            let opacity = a * particle.fade();
+            if opacity <= 0.0 {
+                continue;
+            }
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{}" cy="{}" r="{}"{}/>"#,
+                num(particle.x),
+                num(particle.y),
+                num(particle.size),
+                paint("fill", [r, g, b, opacity])
+            );
+        }
+        if layers {
+            svg.push_str("</g>\n");
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// Writes the opening `<svg>` tag and the background, if any.
+pub(crate) fn begin(svg: &mut String, options: &SvgOptions, canvas_width: f64, canvas_height: f64) {
+    let [x, y, width, height] = options
+        .view_box
+        .unwrap_or([0.0, 0.0, canvas_width, canvas_height]);
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+        num(width),
+        num(height),
+        num(x),
+        num(y),
+        num(width),
+        num(height)
+    );
+    if let Some(background) = options.background {
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}"{}/>"#,
+            num(x),
+            num(y),
+            num(width),
+            num(height),
+            paint("fill", background)
+        );
+    }
+}
+
+/// Attributes shared by every connection line.
+pub(crate) fn line_style(options: &SvgOptions) -> String {
+    let mut style = format!(
+        r#" stroke-width="{}" stroke-linecap="round""#,
+        num(options.stroke_width)
+    );
+    if let Some([r, g, b, _]) = options.stroke {
+        let _ = write!(style, r#" stroke="{}""#, hex([r, g, b]));
+    }
+    style
+}
+
+fn line(svg: &mut String, [x1, y1, x2, y2]: [f64; 4], color: &str, opacity: f64, style: &str) {
+    if opacity <= 0.0 {
+        return;
+    }
+    let _ = write!(
+        svg,
+        r#"<line x1="{}" y1="{}" x2="{}" y2="{}"{color}"#,
+        num(x1),
+        num(y1),
+        num(x2),
+        num(y2)
+    );
+    if opacity < 1.0 {
+        let _ = write!(svg, r#" stroke-opacity="{}""#, num(opacity));
+    }
+    let _ = writeln!(svg, "{style}/>");
+}
+
+/// `fill="#rrggbb"` or similar, with an opacity attribute unless opaque.
+fn paint(attribute: &str, [r, g, b, a]: Rgba) -> String {
+    let mut paint = format!(r#" {attribute}="{}""#, hex([r, g, b]));
+    if a < 1.0 {
+        let _ = write!(paint, r#" {attribute}-opacity="{}""#, num(a));
+    }
+    paint
+}
+
+pub(crate) fn hex(rgb: [f64; 3]) -> String {
+    let [r, g, b] = rgb.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// Numbers rounded to two decimals without trailing zeros, which keeps
+/// documents short at well below a pixel of error.
+pub(crate) fn num(value: f64) -> String {
+    let rounded = (value * 100.0).round() / 100.0;
+    if rounded == 0.0 {
+        return "0".to_string();
+    }
+    let text = format!("{rounded:.2}");
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn rgba(name: &'static str, r: f64, g: f64, b: f64, a: f64) -> Result<Rgba, ParticleError> {
+    params::fraction(name, r)?;
+    params::fraction(name, g)?;
+    params::fraction(name, b)?;
+    params::fraction(name, a)?;
+    Ok([r, g, b, a])
+}
@@ -0,0 +1,440 @@
+use crate::color;
+use crate::connections::LinkScratch;
+use crate::params;
+use crate::svg::{begin, hex, line_style, num};
+use crate::{ParticleError, ParticleSystem, SvgGrouping, SvgOptions};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+// 透明度通道的简化误差，与位置的像素误差分开设置
+const OPACITY_TOLERANCE: f64 = 0.02;
+const MAX_SAMPLES: f64 = 20_000.0;
+
+/// Timing and size limits for `ParticleSystem::to_animated_svg`.
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SvgAnimation {
+    duration_ms: f64,
+    sample_rate: f64,
+    tolerance: f64,
+    max_lines: usize,
+}
+
+impl Default for SvgAnimation {
+    fn default() -> SvgAnimation {
+        SvgAnimation {
+            duration_ms: 10_000.0,
+            sample_rate: 30.0,
+            tolerance: 0.5,
+            max_lines: 200,
+        }
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl SvgAnimation {
+    #[cfg_attr(feature = "wasm", wasm_bindgen(constructor))]
+    pub fn new() -> SvgAnimation {
+        SvgAnimation::default()
+    }
+
+    pub fn duration_ms(&self) -> f64 {
+        self.duration_ms
+    }
+
+    /// Length of the recorded loop.
+    pub fn set_duration_ms(&mut self, duration_ms: f64) -> Result<(), ParticleError> {
+        self.duration_ms = params::positive("duration_ms", duration_ms)?;
+        Ok(())
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Snapshots per second taken while recording, before simplification.
+    pub fn set_sample_rate(&mut self, rate: f64) -> Result<(), ParticleError> {
+        self.sample_rate = params::positive("sample_rate", rate)?;
+        Ok(())
+    }
+
+    pub fn tolerance(&self) -> f64 {
+        self.tolerance
+    }
+
+    /// How far in canvas pixels a simplified path may stray from the
+    /// recorded one. Larger values give smaller documents.
+    pub fn set_tolerance(&mut self, tolerance: f64) -> Result<(), ParticleError> {
+        self.tolerance = params::positive("tolerance", tolerance)?;
+        Ok(())
+    }
+
+    pub fn max_lines(&self) -> usize {
+        self.max_lines
+    }
+
+    /// Connection lines kept in the document, those visible the most
+    /// first; `0` leaves connections out.
+    pub fn set_max_lines(&mut self, max_lines: usize) {
+        self.max_lines = max_lines;
+    }
+}
+
+/// Samples of one particle (`x, y, opacity`) or one connection line
+/// (`x1, y1, x2, y2, opacity`), by frame, for the frames it was visible in.
+struct Track<const N: usize> {
+    color: [f64; 3],
+    size: f64,
+    samples: Vec<(usize, [f64; N])>,
+}
+
+impl<const N: usize> Track<N> {
+    fn new(color: [f64; 3], size: f64) -> Track<N> {
+        Track {
+            color,
+            size,
+            samples: Vec::new(),
+        }
+    }
+
+    fn visibility(&self) -> f64 {
+        self.samples.iter().map(|(_, values)| values[N - 1]).sum()
+    }
+}
+
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
+impl ParticleSystem {
+    /// Runs the simulation for `animation`'s duration and returns an SVG
+    /// document replaying it in a loop with SMIL animations, for pages
+    /// without JavaScript. Each particle and connection line becomes one
+    /// element whose path is simplified to stay within the tolerance.
+    ///
+    /// The mouse keeps its current position throughout. Colours and sizes
+    /// are taken from when an element first appears, and the animation
+    /// jumps back to its first frame at the end of each loop, which is
+    /// seamless when `set_loop_period_ms` matches the duration.
+    pub fn to_animated_svg(&mut self, options: &SvgOptions, animation: &SvgAnimation) -> String {
+        let frames = (animation.duration_ms / 1000.0 * animation.sample_rate)
+            .round()
+            .clamp(1.0, MAX_SAMPLES) as usize;
+        let frame_ms = animation.duration_ms / frames as f64;
+        let mut steps = 0;
+
+        let mut particles: Vec<Track<3>> = Vec::new();
+        let mut particle_tracks: HashMap<u32, usize> = HashMap::new();
+        // 键为两端粒子 id 与线段序号，跨越边缘的连接线有两段
+        let mut lines: BTreeMap<(u32, u32, u8), Track<5>> = BTreeMap::new();
+        let mut scratch = LinkScratch::default();
+        let mut links = Vec::new();
+
+        for frame in 0..=frames {
+            // 按帧时刻取整到步数，循环模式下最后一帧恰好落在周期终点
+            let target = (frame as f64 * frame_ms / self.fixed_step_ms).round() as u64;
+            while steps < target {
+                self.update();
+                steps += 1;
+            }
+
+            for particle in &self.particles {
+                let [r, g, b, a] = self.color_of(particle);
+                let index = *particle_tracks.entry(particle.id).or_insert_with(|| {
+                    particles.push(Track::new([r, g, b], particle.size));
+                    particles.len() - 1
+                });
+                particles[index]
+                    .samples
+                    .push((frame, [particle.x, particle.y, a * particle.fade()]));
+            }
+
+            if animation.max_lines == 0 {
+                continue;
+            }
+            self.collect_links(&mut scratch, &mut links);
+            for link in &links {
+                let p1 = &self.particles[link.a];
+                let p2 = &self.particles[link.b];
+                let [r, g, b, a] = options
+                    .stroke
+                    .unwrap_or_else(|| color::blend(self.color_of(p1), self.color_of(p2), 0.5));
+                let opacity = (a * link.opacity).min(1.0);
+
+                // 以较小的 id 作为起点，粒子交换索引后同一条线仍归入同一轨迹
+                let mut segments = Vec::with_capacity(2);
+                link.segments((p1.x, p1.y), (p2.x, p2.y), |segment| segments.push(segment));
+                let mut key = (p1.id, p2.id);
+                if p1.id > p2.id {
+                    key = (p2.id, p1.id);
+                    segments.reverse();
+                    for segment in &mut segments {
+                        *segment = [segment[2], segment[3], segment[0], segment[1]];
+                    }
+                }
+                for (part, [x1, y1, x2, y2]) in segments.into_iter().enumerate() {
+                    lines
+                        .entry((key.0, key.1, part as u8))
+                        .or_insert_with(|| Track::new([r, g, b], 0.0))
+                        .samples
+                        .push((frame, [x1, y1, x2, y2, opacity]));
+                }
+            }
+        }
+
+        // 只保留可见时间最长的连接线，控制文档大小
+        let mut kept: Vec<_> = lines.into_iter().collect();
+        kept.sort_by(|a, b| b.1.visibility().total_cmp(&a.1.visibility()));
+        kept.truncate(animation.max_lines);
+        kept.sort_by_key(|(key, _)| *key);
+
+        let mut writer = AnimationWriter {
+            svg: String::new(),
+            frames,
+            timing: format!(
+                r#" dur="{}s" repeatCount="indefinite""#,
+                num(animation.duration_ms / 1000.0)
+            ),
+            jump: [self.width / 2.0, self.height / 2.0],
+            tolerance: animation.tolerance,
+        };
+        let layers = options.grouping == SvgGrouping::Layers;
+        begin(&mut writer.svg, options, self.width, self.height);
+        let line_style = line_style(options);
+        if !kept.is_empty() {
+            if layers {
+                let _ = writeln!(writer.svg, r#"<g id="connections"{line_style}>"#);
+            }
+            for (_, track) in &kept {
+                let color = if options.stroke.is_some() {
+                    String::new()
+                } else {
+                    format!(r#" stroke="{}""#, hex(track.color))
+                };
+                let style = if layers { "" } else { line_style.as_str() };
+                writer.line(track, &format!("{color}{style}"));
+            }
+            if layers {
+                writer.svg.push_str("</g>\n");
+            }
+        }
+
+        if layers {
+            writer.svg.push_str("<g id=\"particles\">\n");
+        }
+        for track in &particles {
+            writer.circle(track);
+        }
+        if layers {
+            writer.svg.push_str("</g>\n");
+        }
+
+        writer.svg.push_str("</svg>\n");
+        writer.svg
+    }
+}
+
+struct AnimationWriter {
+    svg: String,
+    frames: usize,
+    timing: String,
+    jump: [f64; 2],
+    tolerance: f64,
+}
+
+impl AnimationWriter {
+    fn circle(&mut self, track: &Track<3>) {
+        let keys = self.keyframes(track, [self.tolerance, self.tolerance, OPACITY_TOLERANCE]);
+        if keys.iter().all(|(_, values)| values[2] <= 0.0) {
+            return;
+        }
+
+        let _ = write!(
+            self.svg,
+            r#"<circle r="{}" fill="{}""#,
+            num(track.size),
+            hex(track.color)
+        );
+        let translate: Vec<String> = keys
+            .iter()
+            .map(|(_, v)| format!("{},{}", num(v[0]), num(v[1])))
+            .collect();
+        let moves = translate.iter().any(|value| *value != translate[0]);
+        if !moves {
+            let [x, y, _] = keys[0].1;
+            let _ = write!(self.svg, r#" cx="{}" cy="{}""#, num(x), num(y));
+        }
+        let opacity = self.attribute(&keys, "fill-opacity", 2);
+        self.svg.push('>');
+        if moves {
+            let _ = write!(
+                self.svg,
+                r#"<animateTransform attributeName="transform" type="translate" values="{}" keyTimes="{}"{}/>"#,
+                translate.join(";"),
+                key_times(&keys, self.frames),
+                self.timing
+            );
+        }
+        self.svg.push_str(&opacity);
+        self.svg.push_str("</circle>\n");
+    }
+
+    fn line(&mut self, track: &Track<5>, style: &str) {
+        let t = self.tolerance;
+        let keys = self.keyframes(track, [t, t, t, t, OPACITY_TOLERANCE]);
+        self.svg.push_str("<line");
+        let animations: String = ["x1", "y1", "x2", "y2", "stroke-opacity"]
+            .iter()
+            .enumerate()
+            .map(|(channel, name)| self.attribute(&keys, name, channel))
+            .collect();
+        let _ = writeln!(self.svg, "{style}>{animations}</line>");
+    }
+
+    /// Writes `name` as a plain attribute when it never changes, otherwise
+    /// returns an `<animate>` element for it.
+    fn attribute<const N: usize>(
+        &mut self,
+        keys: &[(usize, [f64; N])],
+        name: &str,
+        channel: usize,
+    ) -> String {
+        let values: Vec<String> = keys.iter().map(|(_, v)| num(v[channel])).collect();
+        if values.iter().all(|value| *value == values[0]) {
+            // 不透明度为 1 是默认值，无需写出
+            if !(name.ends_with("opacity") && values[0] == "1") {
+                let _ = write!(self.svg, r#" {name}="{}""#, values[0]);
+            }
+            return String::new();
+        }
+        format!(
+            r#"<animate attributeName="{name}" values="{}" keyTimes="{}"{}/>"#,
+            values.join(";"),
+            key_times(keys, self.frames),
+            self.timing
+        )
+    }
+
+    /// Simplified keyframes covering the whole loop. Frames where the track
+    /// is missing get zero opacity, and jumps across the canvas (wrapping
+    /// particles, lines switching sides) become instant changes.
+    fn keyframes<const N: usize>(
+        &self,
+        track: &Track<N>,
+        tolerance: [f64; N],
+    ) -> Vec<(usize, [f64; N])> {
+        let hidden = |mut values: [f64; N]| {
+            values[N - 1] = 0.0;
+            values
+        };
+
+        // 按缺失的帧和跳变拆成连续的片段，每段单独简化
+        let samples = &track.samples;
+        let mut runs = Vec::new();
+        let mut start = 0;
+        for i in 1..=samples.len() {
+            let split = i == samples.len()
+                || samples[i].0 != samples[i - 1].0 + 1
+                || self.jumps(&samples[i - 1].1, &samples[i].1);
+            if split {
+                runs.push(&samples[start..i]);
+                start = i;
+            }
+        }
+
+        let mut keys: Vec<(usize, [f64; N])> = Vec::new();
+        let mut previous: Option<&[(usize, [f64; N])]> = None;
+        for run in runs {
+            let (first_frame, first) = run[0];
+            match previous {
+                None if first_frame > 0 => {
+                    keys.push((0, hidden(first)));
+                    keys.push((first_frame, hidden(first)));
+                }
+                Some(previous) => {
+                    let (last_frame, last) = previous[previous.len() - 1];
+                    if last_frame + 1 == first_frame {
+                        // 跳变：沿原方向继续运动一帧，再瞬间出现在新位置
+                        let mut next = last;
+                        if let Some(&(_, before)) =
+                            previous.len().checked_sub(2).map(|i| &previous[i])
+                        {
+                            for c in 0..N - 1 {
+                                next[c] += last[c] - before[c];
+                            }
+                        }
+                        keys.push((first_frame, next));
+                    } else {
+                        keys.push((last_frame, hidden(last)));
+                        keys.push((first_frame, hidden(first)));
+                    }
+                }
+                None => {}
+            }
+            keys.extend(simplify(run, &tolerance).into_iter().map(|i| run[i]));
+            previous = Some(run);
+        }
+
+        let (last_frame, last) = *keys.last().expect("tracks have at least one sample");
+        if last_frame < self.frames {
+            keys.push((last_frame, hidden(last)));
+            keys.push((self.frames, hidden(last)));
+        }
+
+        // 去掉与前后关键帧取值相同的中间帧，例如淡出后补上的隐藏帧
+        let mut deduped: Vec<(usize, [f64; N])> = Vec::with_capacity(keys.len());
+        for (i, key) in keys.iter().enumerate() {
+            let redundant = deduped.last().is_some_and(|previous| previous.1 == key.1)
+                && keys.get(i + 1).is_some_and(|next| next.1 == key.1);
+            if !redundant {
+                deduped.push(*key);
+            }
+        }
+        deduped
+    }
+
+    fn jumps<const N: usize>(&self, a: &[f64; N], b: &[f64; N]) -> bool {
+        (0..N - 1).any(|c| (a[c] - b[c]).abs() > self.jump[c % 2])
+    }
+}
+
+/// Ramer–Douglas–Peucker on time series: indices of the samples to keep so
+/// that linear interpolation between them stays within `tolerance` of every
+/// sample, per channel.
+fn simplify<const N: usize>(samples: &[(usize, [f64; N])], tolerance: &[f64; N]) -> Vec<usize> {
+    let mut keep = vec![false; samples.len()];
+    keep[0] = true;
+    keep[samples.len() - 1] = true;
+
+    let mut stack = vec![(0, samples.len() - 1)];
+    while let Some((a, b)) = stack.pop() {
+        let (ta, va) = samples[a];
+        let (tb, vb) = samples[b];
+        let mut worst = (1.0, None);
+        for (i, &(t, values)) in samples.iter().enumerate().take(b).skip(a + 1) {
+            let f = (t - ta) as f64 / (tb - ta) as f64;
+            for c in 0..N {
+                let error = (va[c] + (vb[c] - va[c]) * f - values[c]).abs() / tolerance[c];
+                if error > worst.0 {
+                    worst = (error, Some(i));
+                }
+            }
+        }
+        if let Some(i) = worst.1 {
+            keep[i] = true;
+            stack.push((a, i));
+            stack.push((i, b));
+        }
+    }
+    (0..samples.len()).filter(|&i| keep[i]).collect()
+}
+
+fn key_times<const N: usize>(keys: &[(usize, [f64; N])], frames: usize) -> String {
+    let times: Vec<String> = keys
+        .iter()
+        .map(|&(frame, _)| {
+            let text = format!("{:.4}", frame as f64 / frames as f64);
+            text.trim_end_matches('0').trim_end_matches('.').to_string()
+        })
+        .collect();
+    times.join(";")
+}
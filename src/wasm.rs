@@ -0,0 +1,89 @@
+//! JS-facing helpers that need `js_sys` types. Everything else is exported
+//! directly from the core types via `wasm_bindgen` attributes.
+
+use crate::{ParticleError, ParticleSystem, Renderer};
+use js_sys::{Float32Array, Float64Array, Uint32Array, Uint8ClampedArray};
+use wasm_bindgen::prelude::*;
+
+impl From<ParticleError> for JsValue {
+    fn from(error: ParticleError) -> JsValue {
+        js_sys::Error::new(&error.to_string()).into()
+    }
+}
+
+#[wasm_bindgen]
+impl ParticleSystem {
+    pub fn get_particles(&self) -> Float64Array {
+        Float64Array::from(self.particle_data().as_slice())
+    }
+
+    /// Like `get_particles`, but positions are blended between the last two
+    /// fixed steps by `interpolation_alpha`.
+    pub fn get_interpolated_particles(&self) -> Float64Array {
+        Float64Array::from(self.interpolated_particle_data().as_slice())
+    }
+
+    pub fn get_mouse_connections(&self) -> Float64Array {
+        Float64Array::from(self.mouse_connection_data().as_slice())
+    }
+
+    pub fn calculate_connections(&self) -> Float64Array {
+        Float64Array::from(self.connection_data().as_slice())
+    }
+
+    pub fn particles_view(&self) -> Float32Array {
+        // SAFETY: 视图只在下一次 prepare_frame 或内存增长前有效，调用方需在此之前读取
+        unsafe { Float32Array::view(self.particle_buffer()) }
+    }
+
+    pub fn ids_view(&self) -> Uint32Array {
+        // SAFETY: 同 particles_view
+        unsafe { Uint32Array::view(self.id_buffer()) }
+    }
+
+    pub fn connections_view(&self) -> Float32Array {
+        // SAFETY: 同 particles_view
+        unsafe { Float32Array::view(self.connection_buffer()) }
+    }
+
+    pub fn connection_indices_view(&self) -> Uint32Array {
+        // SAFETY: 同 particles_view
+        unsafe { Uint32Array::view(self.connection_index_buffer()) }
+    }
+
+    pub fn connection_opacities_view(&self) -> Float32Array {
+        // SAFETY: 同 particles_view
+        unsafe { Float32Array::view(self.connection_opacity_buffer()) }
+    }
+
+    pub fn instances_view(&self) -> Float32Array {
+        // SAFETY: 同 particles_view
+        unsafe { Float32Array::view(self.instance_buffer()) }
+    }
+
+    pub fn line_vertices_view(&self) -> Float32Array {
+        // SAFETY: 同 particles_view
+        unsafe { Float32Array::view(self.line_vertex_buffer()) }
+    }
+
+    pub fn mouse_connections_view(&self) -> Float32Array {
+        // SAFETY: 同 particles_view
+        unsafe { Float32Array::view(self.mouse_connection_buffer()) }
+    }
+}
+
+#[wasm_bindgen]
+impl Renderer {
+    /// The last frame, for `new ImageData(view, width, height)`.
+    pub fn pixels_view(&self) -> Uint8ClampedArray {
+        // SAFETY: 视图只在下一次 render、resize 或内存增长前有效
+        unsafe { Uint8ClampedArray::view(self.pixels()) }
+    }
+}
+
+/// The module's linear memory, for building typed arrays over the pointers
+/// returned by `particles_ptr` and friends.
+#[wasm_bindgen]
+pub fn wasm_memory() -> JsValue {
+    wasm_bindgen::memory()
+}
@@ -0,0 +1,102 @@
+use floating_particles::{BoundaryMode, EmitterShape, ParticleSystem, SvgAnimation, SvgOptions};
+
+fn animation(duration_ms: f64) -> SvgAnimation {
+    let mut animation = SvgAnimation::new();
+    animation.set_duration_ms(duration_ms).unwrap();
+    animation
+}
+
+/// The `name="..."` attribute values of every `<animate*>` element animating
+/// `attribute`.
+fn animated<'a>(svg: &'a str, attribute: &str, name: &str) -> Vec<&'a str> {
+    let tag = format!(r#"attributeName="{attribute}""#);
+    let prefix = format!(r#" {name}=""#);
+    svg.split("<animate")
+        .filter(|element| element.contains(&tag))
+        .map(|element| {
+            let start = element.find(&prefix).unwrap() + prefix.len();
+            &element[start..start + element[start..].find('"').unwrap()]
+        })
+        .collect()
+}
+
+#[test]
+fn particles_loop_over_the_duration() {
+    let mut system = ParticleSystem::with_seed(400.0, 300.0, 40, 80.0, 8);
+    let svg = system.to_animated_svg(&SvgOptions::new(), &animation(2000.0));
+
+    assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="400" height="300""#));
+    assert_eq!(svg.matches("<circle ").count(), 40);
+    assert!(svg.contains(r#"dur="2s" repeatCount="indefinite""#));
+    for times in animated(&svg, "transform", "keyTimes") {
+        assert!(times.starts_with("0;") && times.ends_with(";1"), "{times}");
+    }
+}
+
+#[test]
+fn straight_motion_needs_two_keyframes() {
+    let mut system = ParticleSystem::with_seed(400.0, 300.0, 0, 80.0, 8);
+    system
+        .add_particle_with_velocity(100.0, 100.0, 1.0, 0.5)
+        .unwrap();
+    let svg = system.to_animated_svg(&SvgOptions::new(), &animation(1000.0));
+
+    assert_eq!(animated(&svg, "transform", "values"), ["100,100;160,130"]);
+}
+
+#[test]
+fn wrapping_particles_jump_instead_of_crossing_the_canvas() {
+    let mut system = ParticleSystem::with_seed(300.0, 200.0, 0, 80.0, 8);
+    system.set_boundary_mode(BoundaryMode::Wrap);
+    system
+        .add_particle_with_velocity(280.0, 100.0, 2.0, 0.0)
+        .unwrap();
+    let svg = system.to_animated_svg(&SvgOptions::new(), &animation(1000.0));
+
+    let values = animated(&svg, "transform", "values")[0];
+    let times = animated(&svg, "transform", "keyTimes")[0];
+    assert_eq!(values, "280,100;296,100;300,100;0,100;100,100");
+    assert_eq!(times, "0;0.1333;0.1667;0.1667;1");
+}
+
+#[test]
+fn late_particles_start_hidden() {
+    let mut system = ParticleSystem::with_seed(400.0, 300.0, 0, 80.0, 8);
+    let id = system
+        .add_emitter(EmitterShape::Point, 200.0, 150.0, 0.0, 0.0)
+        .unwrap();
+    system.set_emitter_rate(id, 4.0).unwrap();
+    let svg = system.to_animated_svg(&SvgOptions::new(), &animation(1000.0));
+
+    let opacities = animated(&svg, "fill-opacity", "values");
+    assert!(opacities.len() >= 3);
+    assert!(
+        opacities[1..]
+            .iter()
+            .all(|values| values.starts_with("0;0;")),
+        "{opacities:?}"
+    );
+}
+
+#[test]
+fn connection_lines_are_capped() {
+    let mut system = ParticleSystem::with_seed(400.0, 300.0, 60, 120.0, 8);
+    let mut limited = animation(500.0);
+    limited.set_max_lines(5);
+    let svg = system.to_animated_svg(&SvgOptions::new(), &limited);
+    assert_eq!(svg.matches("<line").count(), 5);
+
+    limited.set_max_lines(0);
+    let svg = system.to_animated_svg(&SvgOptions::new(), &limited);
+    assert!(!svg.contains("<line"));
+    assert!(!svg.contains("connections"));
+}
+
+#[test]
+fn invalid_settings_are_rejected() {
+    let mut animation = SvgAnimation::new();
+    assert!(animation.set_duration_ms(0.0).is_err());
+    assert!(animation.set_sample_rate(f64::NAN).is_err());
+    assert!(animation.set_tolerance(-1.0).is_err());
+    assert_eq!(animation, SvgAnimation::new());
+}
@@ -0,0 +1,128 @@
+use floating_particles::{AttractorKind, Falloff, ParticleError, ParticleSystem};
+
+/// Displacement over one step of a resting particle `distance` to the left
+/// of an attractor with radius 200 and strength 1.
+fn displacement(kind: AttractorKind, falloff: Falloff, distance: f64) -> (f64, f64) {
+    let mut system = ParticleSystem::with_seed(4000.0, 4000.0, 0, 50.0, 5);
+    let id = system
+        .add_particle_with_velocity(2000.0, 2000.0, 0.0, 0.0)
+        .unwrap();
+    system
+        .add_attractor(2000.0 + distance, 2000.0, 200.0, 1.0, kind, falloff)
+        .unwrap();
+    system.update();
+    let particle = system.particle(id).unwrap();
+    (particle.x - 2000.0, particle.y - 2000.0)
+}
+
+fn assert_close(actual: (f64, f64), expected: (f64, f64)) {
+    assert!(
+        (actual.0 - expected.0).abs() < 1e-12 && (actual.1 - expected.1).abs() < 1e-12,
+        "{actual:?} != {expected:?}"
+    );
+}
+
+#[test]
+fn non_finite_attractor_inputs_are_rejected() {
+    let mut system = ParticleSystem::with_seed(400.0, 300.0, 50, 80.0, 5);
+    let add = |system: &mut ParticleSystem, x: f64, radius: f64, strength: f64| {
+        system.add_attractor(
+            x,
+            150.0,
+            radius,
+            strength,
+            AttractorKind::Attract,
+            Falloff::Linear,
+        )
+    };
+    assert!(matches!(
+        add(&mut system, f64::NAN, 100.0, 0.5),
+        Err(ParticleError::InvalidValue { name: "x", .. })
+    ));
+    assert!(add(&mut system, 200.0, -1.0, 0.5).is_err());
+    assert!(add(&mut system, 200.0, f64::INFINITY, 0.5).is_err());
+    assert!(add(&mut system, 200.0, 100.0, f64::NAN).is_err());
+    assert_eq!(system.attractor_count(), 0);
+
+    let id = add(&mut system, 200.0, 100.0, 0.5).unwrap();
+    assert!(system.move_attractor(id, f64::NAN, 0.0).is_err());
+    assert!(system.set_attractor_radius(id, f64::NAN).is_err());
+    assert!(system.set_attractor_strength(id, f64::INFINITY).is_err());
+    assert_eq!(system.move_attractor(id + 1, 10.0, 10.0), Ok(false));
+    assert_eq!(system.move_attractor(id, 10.0, 10.0), Ok(true));
+
+    for _ in 0..10 {
+        system.update();
+    }
+    assert!(system.particle_data().iter().all(|v| v.is_finite()));
+}
+
+#[test]
+fn each_kind_pushes_in_its_own_direction() {
+    let constant = Falloff::Constant;
+    assert_close(
+        displacement(AttractorKind::Attract, constant, 100.0),
+        (1.0, 0.0),
+    );
+    assert_close(
+        displacement(AttractorKind::Repel, constant, 100.0),
+        (-1.0, 0.0),
+    );
+    assert_close(
+        displacement(AttractorKind::Vortex, constant, 100.0),
+        (0.0, -1.0),
+    );
+    assert_close(
+        displacement(AttractorKind::Attract, constant, 250.0),
+        (0.0, 0.0),
+    );
+}
+
+#[test]
+fn falloff_shapes_the_force() {
+    // 距中心 150、半径 200 时 t = 0.25
+    let at = |falloff| displacement(AttractorKind::Attract, falloff, 150.0).0;
+    assert!((at(Falloff::Constant) - 1.0).abs() < 1e-12);
+    assert!((at(Falloff::Linear) - 0.25).abs() < 1e-12);
+    assert!((at(Falloff::Quadratic) - 0.0625).abs() < 1e-12);
+    assert!((at(Falloff::Smooth) - 0.15625).abs() < 1e-12);
+}
+
+#[test]
+fn lifetimes_remove_attractors() {
+    let mut system = ParticleSystem::with_seed(400.0, 300.0, 0, 80.0, 5);
+    let add = |system: &mut ParticleSystem| {
+        system
+            .add_attractor(
+                200.0,
+                150.0,
+                100.0,
+                0.5,
+                AttractorKind::Repel,
+                Falloff::Linear,
+            )
+            .unwrap()
+    };
+    let short = add(&mut system);
+    let permanent = add(&mut system);
+    assert!(system.set_attractor_lifetime(short, f64::NAN).is_err());
+    assert!(system.set_attractor_lifetime(short, -1.0).is_err());
+    assert_eq!(system.set_attractor_lifetime(short, 90.0), Ok(true));
+    assert_eq!(system.set_attractor_lifetime(permanent, 50.0), Ok(true));
+    assert!(system.clear_attractor_lifetime(permanent));
+    assert_eq!(
+        system.set_attractor_lifetime(permanent + 1, 50.0),
+        Ok(false)
+    );
+
+    for _ in 0..5 {
+        system.update();
+    }
+    assert_eq!(system.attractor_ids(), vec![short, permanent]);
+    system.update();
+    assert_eq!(system.attractor_ids(), vec![permanent]);
+    for _ in 0..100 {
+        system.update();
+    }
+    assert_eq!(system.attractor_ids(), vec![permanent]);
+}
@@ -0,0 +1,216 @@
+mod common;
+
+use common::png::decode_png;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn render(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_render-particles"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn scratch(name: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR"))
+        .join("cli")
+        .join(name);
+    let _ = fs::remove_dir_all(&path);
+    let _ = fs::remove_file(&path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    path
+}
+
+fn y4m(args: &[&str]) -> Vec<u8> {
+    let output = render(&[args, &["--output", "-"]].concat());
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output.stdout
+}
+
+#[test]
+fn png_sequence_has_one_file_per_frame() {
+    let dir = scratch("frames");
+    let output = render(&[
+        "--seed",
+        "1",
+        "--size",
+        "80x60",
+        "--scale",
+        "2",
+        "--duration",
+        "0.5",
+        "--fps",
+        "10",
+        "--output",
+        dir.to_str().unwrap(),
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let mut names: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    names.sort();
+    assert_eq!(names.len(), 5);
+    assert_eq!(names[0], "frame-00000.png");
+
+    let image = decode_png(&fs::read(dir.join("frame-00004.png")).unwrap()).unwrap();
+    assert_eq!((image.width, image.height), (160, 120));
+    assert!(image.pixels.chunks(4).all(|pixel| pixel[3] == 255));
+}
+
+#[test]
+fn y4m_stream_has_a_header_and_fixed_size_frames() {
+    let stream = y4m(&[
+        "--preset",
+        "snow",
+        "--seed",
+        "2",
+        "--size",
+        "40x30",
+        "--duration",
+        "1",
+        "--fps",
+        "12",
+    ]);
+    let header = b"YUV4MPEG2 W40 H30 F12:1 Ip A1:1 C444\n";
+    assert!(stream.starts_with(header));
+
+    let frame = b"FRAME\n".len() + 40 * 30 * 3;
+    assert_eq!(stream.len(), header.len() + 12 * frame);
+    assert_eq!(&stream[header.len() + 11 * frame..][..6], b"FRAME\n");
+}
+
+#[test]
+fn gif_output_is_a_looping_animation() {
+    let path = scratch("loop.gif");
+    let output = render(&[
+        "--seed",
+        "3",
+        "--size",
+        "64x48",
+        "--duration",
+        "0.3",
+        "--output",
+        path.to_str().unwrap(),
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let gif = fs::read(&path).unwrap();
+    assert!(gif.starts_with(b"GIF89a"));
+    assert_eq!(&gif[6..10], &[64, 0, 48, 0]);
+    assert!(gif.windows(11).any(|window| window == b"NETSCAPE2.0"));
+    assert_eq!(gif.last(), Some(&0x3b));
+}
+
+#[test]
+fn gif_delays_stay_exact_past_eleven_minutes() {
+    let config = scratch("empty.json");
+    fs::write(&config, r#"{"particle_count": 0}"#).unwrap();
+    let path = scratch("long.gif");
+    let output = render(&[
+        "--config",
+        config.to_str().unwrap(),
+        "--size",
+        "8x8",
+        "--duration",
+        "700",
+        "--fps",
+        "3",
+        "--output",
+        path.to_str().unwrap(),
+    ]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    // 每帧的图形控制扩展块后紧跟两字节延迟
+    let gif = fs::read(&path).unwrap();
+    let delays: Vec<u16> = gif
+        .windows(6)
+        .filter(|window| window[..4] == [0x21, 0xf9, 0x04, 0x04])
+        .map(|window| u16::from_le_bytes([window[4], window[5]]))
+        .collect();
+    assert_eq!(delays.len(), 2100);
+    assert_eq!(
+        delays.iter().map(|&delay| u32::from(delay)).sum::<u32>(),
+        70000
+    );
+    assert!(delays[2000..]
+        .iter()
+        .all(|&delay| delay == 33 || delay == 34));
+}
+
+#[test]
+fn seeded_renders_are_reproducible_and_follow_the_cursor() {
+    let cursor = scratch("cursor.txt");
+    fs::write(&cursor, "# seconds x y\n0 10 10\n0.5 150 100\n0.8 off\n").unwrap();
+    let args = [
+        "--seed",
+        "landing",
+        "--size",
+        "160x100",
+        "--duration",
+        "1",
+        "--fps",
+        "15",
+    ];
+
+    let plain = y4m(&args);
+    assert_eq!(y4m(&args), plain);
+
+    let with_cursor = y4m(&[args.as_slice(), &["--cursor", cursor.to_str().unwrap()]].concat());
+    assert_eq!(with_cursor.len(), plain.len());
+    assert_ne!(with_cursor, plain);
+}
+
+#[test]
+fn seed_flag_matches_config_seeds() {
+    let args = ["--size", "80x60", "--duration", "0.2"];
+    let flag = y4m(&[args.as_slice(), &["--seed", "123"]].concat());
+
+    for seed in ["123", r#""123""#] {
+        let config = scratch("seed.json");
+        fs::write(&config, format!(r#"{{"seed": {seed}}}"#)).unwrap();
+        let from_config = y4m(&[args.as_slice(), &["--config", config.to_str().unwrap()]].concat());
+        assert_eq!(from_config, flag);
+    }
+}
+
+#[test]
+fn bad_arguments_are_reported() {
+    let output = render(&["--size", "10by10", "--output", "-"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid size `10by10`"));
+
+    let output = render(&["--preset", "fog", "--output", "-"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown preset `fog`"));
+
+    let output = render(&["--fps", "29.97", "--output", "-"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("--fps must be a whole number between 1 and 240, got `29.97`"));
+
+    let cursor = scratch("bad-cursor.txt");
+    fs::write(&cursor, "1 10 10\n0 20 20\n").unwrap();
+    let output = render(&["--cursor", cursor.to_str().unwrap(), "--output", "-"]);
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("line 2: keyframes must be in time order")
+    );
+}
@@ -0,0 +1,172 @@
+use floating_particles::{ColorMode, ParticleConfig, ParticleError, ParticleField, ParticleSystem};
+
+const RED: [f64; 4] = [1.0, 0.0, 0.0, 1.0];
+const BLUE: [f64; 4] = [0.0, 0.0, 1.0, 0.5];
+
+#[test]
+fn palette_colors_are_stable_per_particle() {
+    let mut system = ParticleSystem::with_seed(800.0, 600.0, 200, 100.0, 6);
+    system.set_palette([RED, BLUE].concat()).unwrap();
+
+    let ids = system.particle_ids();
+    let before: Vec<_> = ids.iter().map(|&id| system.particle_color(id)).collect();
+    assert!(before.contains(&RED.to_vec()));
+    assert!(before.contains(&BLUE.to_vec()));
+
+    system.remove_particle(ids[0]);
+    for _ in 0..10 {
+        system.update();
+    }
+    for (id, color) in ids.iter().zip(&before).skip(1) {
+        assert_eq!(&system.particle_color(*id), color);
+    }
+}
+
+#[test]
+fn speed_gradient_maps_the_speed_range() {
+    let mut system = ParticleSystem::with_seed(800.0, 600.0, 0, 100.0, 6);
+    system.set_color_mode(ColorMode::Speed);
+    system
+        .set_color_gradient([[0.0].as_slice(), &RED, &[1.0], &BLUE].concat())
+        .unwrap();
+    system.set_color_speed_range(0.0, 2.0).unwrap();
+
+    let still = system
+        .add_particle_with_velocity(100.0, 100.0, 0.0, 0.0)
+        .unwrap();
+    let fast = system
+        .add_particle_with_velocity(300.0, 300.0, 0.0, 4.0)
+        .unwrap();
+    let middle = system
+        .add_particle_with_velocity(500.0, 300.0, 1.0, 0.0)
+        .unwrap();
+
+    assert_eq!(system.particle_color(still), RED);
+    assert_eq!(system.particle_color(fast), BLUE);
+    assert_eq!(system.particle_color(middle), vec![0.5, 0.0, 0.5, 0.75]);
+}
+
+#[test]
+fn mouse_gradient_follows_attraction_strength() {
+    let mut system = ParticleSystem::with_seed(800.0, 600.0, 0, 100.0, 6);
+    system.set_color_mode(ColorMode::MouseAttraction);
+    system
+        .set_color_gradient([[0.0].as_slice(), &RED, &[1.0], &BLUE].concat())
+        .unwrap();
+    let near = system
+        .add_particle_with_velocity(400.0, 300.0, 0.0, 0.0)
+        .unwrap();
+    let far = system
+        .add_particle_with_velocity(10.0, 10.0, 0.0, 0.0)
+        .unwrap();
+
+    assert_eq!(system.particle_color(near), RED);
+
+    system.update_mouse_position(400.0, 300.0);
+    assert_eq!(system.particle_color(near), BLUE);
+    assert_eq!(system.particle_color(far), RED);
+}
+
+#[test]
+fn color_field_extends_particle_and_connection_records() {
+    let mut system = ParticleSystem::with_seed(800.0, 600.0, 0, 100.0, 6);
+    system.set_palette(RED.to_vec()).unwrap();
+    system
+        .add_particle_with_velocity(100.0, 100.0, 0.0, 0.0)
+        .unwrap();
+    system
+        .add_particle_with_velocity(150.0, 100.0, 0.0, 0.0)
+        .unwrap();
+    system.set_particle_fields(ParticleField::Age as u32 | ParticleField::Color as u32);
+
+    assert_eq!(system.particle_stride(), 9);
+    assert_eq!(system.connection_stride(), 9);
+
+    let particles = system.particle_data();
+    assert_eq!(particles.len(), 18);
+    assert_eq!(&particles[5..9], &RED);
+
+    let connections = system.connection_data();
+    assert_eq!(connections.len(), 9);
+    assert_eq!(&connections[5..9], &RED);
+
+    system.prepare_frame(false);
+    assert_eq!(system.particle_buffer().len(), 18);
+    assert_eq!(system.connection_buffer().len(), 9);
+}
+
+#[test]
+fn connection_colors_blend_their_endpoints() {
+    let mut system = ParticleSystem::with_seed(800.0, 600.0, 0, 100.0, 6);
+    system.set_color_mode(ColorMode::Speed);
+    system
+        .set_color_gradient([[0.0].as_slice(), &RED, &[1.0], &BLUE].concat())
+        .unwrap();
+    system
+        .add_particle_with_velocity(100.0, 100.0, 0.0, 0.0)
+        .unwrap();
+    system
+        .add_particle_with_velocity(150.0, 100.0, 0.0, 2.0)
+        .unwrap();
+    system.set_particle_fields(ParticleField::Color as u32);
+
+    assert_eq!(&system.connection_data()[5..9], &[0.5, 0.0, 0.5, 0.75]);
+}
+
+#[test]
+fn invalid_color_rules_are_rejected() {
+    let mut system = ParticleSystem::with_seed(800.0, 600.0, 0, 100.0, 6);
+
+    assert!(system.set_palette(vec![]).is_err());
+    assert!(system.set_palette(vec![1.0, 0.0, 0.0]).is_err());
+    assert!(matches!(
+        system.set_palette(vec![1.5, 0.0, 0.0, 1.0]),
+        Err(ParticleError::InvalidValue {
+            name: "color.palette",
+            ..
+        })
+    ));
+    assert!(system
+        .set_color_gradient([[0.8].as_slice(), &RED, &[0.2], &BLUE].concat())
+        .is_err());
+    assert_eq!(system.palette(), vec![1.0, 1.0, 1.0, 1.0]);
+}
+
+#[test]
+fn color_rules_round_trip_through_json() {
+    let mut config = ParticleConfig::default();
+    config.color.mode = ColorMode::MouseAttraction;
+    config.color.palette = vec![RED, BLUE];
+    config.color.gradient = vec![(0.0, BLUE), (0.25, RED), (1.0, BLUE)];
+
+    let parsed = ParticleConfig::from_json(&config.to_json()).unwrap();
+    assert_eq!(parsed, config);
+
+    let system = ParticleSystem::from_config(&config).unwrap();
+    assert_eq!(system.config().color, config.color);
+
+    let error = ParticleConfig::from_json(r#"{"color": {"palette": [[1, 0, 0]]}}"#).unwrap_err();
+    assert!(matches!(error, ParticleError::Config { ref path, .. } if path == "color.palette"));
+}
+
+#[test]
+fn empty_color_lists_in_configs_are_rejected() {
+    for json in [
+        r#"{"color": {"palette": []}}"#,
+        r#"{"color": {"gradient": []}}"#,
+    ] {
+        let error = ParticleConfig::from_json(json).unwrap_err();
+        assert!(matches!(
+            error,
+            ParticleError::InvalidValue {
+                expected: "at least one entry",
+                ..
+            }
+        ));
+    }
+
+    let mut config = ParticleConfig::default();
+    config.color.palette.clear();
+    assert!(config.validate().is_err());
+    assert!(ParticleSystem::from_config(&config).is_err());
+}
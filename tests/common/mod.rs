@@ -0,0 +1,4 @@
+// 每个测试只用到其中一部分辅助函数
+#![allow(dead_code, unused_imports)]
+
+pub mod png;
@@ -0,0 +1,386 @@
+//! PNG decoding for the tests: non-interlaced 8-bit RGB and RGBA files,
+//! plus the diff images written when a golden frame does not match.
+
+#[path = "../../src/bin/render-particles/png.rs"]
+mod encoder;
+
+pub use encoder::encode_png;
+use encoder::{
+    adler32, crc32, predict, DISTANCE_BASE, DISTANCE_EXTRA, LENGTH_BASE, LENGTH_EXTRA, SIGNATURE,
+};
+
+/// An RGBA8 image with rows stored top to bottom.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// Decodes a PNG file into RGBA8, adding opaque alpha to RGB images.
+pub fn decode_png(data: &[u8]) -> Result<Image, String> {
+    if !data.starts_with(&SIGNATURE) {
+        return Err(image_error("not a PNG file"));
+    }
+
+    let mut header = None;
+    let mut compressed = Vec::new();
+    let mut rest = &data[SIGNATURE.len()..];
+    loop {
+        if rest.len() < 12 {
+            return Err(image_error("truncated chunk"));
+        }
+        let length = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        let kind = &rest[4..8];
+        let Some(body) = rest.get(8..8 + length) else {
+            return Err(image_error("truncated chunk"));
+        };
+        let Some(stored_crc) = rest.get(8 + length..12 + length) else {
+            return Err(image_error("truncated chunk"));
+        };
+        if crc32(&rest[4..8 + length]).to_be_bytes() != stored_crc {
+            return Err(image_error("chunk checksum mismatch"));
+        }
+
+        match kind {
+            b"IHDR" => header = Some(parse_header(body)?),
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ if kind[0].is_ascii_uppercase() => {
+                return Err(image_error(&format!(
+                    "unsupported critical chunk `{}`",
+                    String::from_utf8_lossy(kind)
+                )))
+            }
+            _ => {}
+        }
+        rest = &rest[12 + length..];
+    }
+
+    let (width, height, channels) = header.ok_or_else(|| image_error("missing IHDR chunk"))?;
+    let stride = width as usize * channels;
+    let raw = inflate_zlib(&compressed)?;
+    if raw.len() != (stride + 1) * height as usize {
+        return Err(image_error("image data has the wrong length"));
+    }
+    let rows = unfilter(stride, channels, &raw)?;
+
+    let pixels = if channels == 4 {
+        rows
+    } else {
+        rows.chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+            .collect()
+    };
+    Ok(Image {
+        width,
+        height,
+        pixels,
+    })
+}
+
+fn parse_header(body: &[u8]) -> Result<(u32, u32, usize), String> {
+    if body.len() != 13 {
+        return Err(image_error("malformed IHDR chunk"));
+    }
+    let width = u32::from_be_bytes([body[0], body[1], body[2], body[3]]);
+    let height = u32::from_be_bytes([body[4], body[5], body[6], body[7]]);
+    let channels = match (body[8], body[9]) {
+        (8, 6) => 4,
+        (8, 2) => 3,
+        (depth, color) => {
+            return Err(image_error(&format!(
+                "unsupported format: bit depth {depth}, colour type {color}"
+            )))
+        }
+    };
+    if body[12] != 0 {
+        return Err(image_error("interlaced images are not supported"));
+    }
+    if width == 0 || height == 0 {
+        return Err(image_error("empty image"));
+    }
+    Ok((width, height, channels))
+}
+
+fn unfilter(stride: usize, channels: usize, raw: &[u8]) -> Result<Vec<u8>, String> {
+    let rows = raw.len() / (stride + 1);
+    let mut out = vec![0u8; stride * rows];
+    for row in 0..rows {
+        let kind = raw[row * (stride + 1)];
+        if kind > 4 {
+            return Err(image_error(&format!("unknown filter type {kind}")));
+        }
+        let line = &raw[row * (stride + 1) + 1..(row + 1) * (stride + 1)];
+        for i in 0..stride {
+            let left = if i >= channels {
+                out[row * stride + i - channels]
+            } else {
+                0
+            };
+            let up = if row > 0 {
+                out[(row - 1) * stride + i]
+            } else {
+                0
+            };
+            let up_left = if row > 0 && i >= channels {
+                out[(row - 1) * stride + i - channels]
+            } else {
+                0
+            };
+            out[row * stride + i] = line[i].wrapping_add(predict(kind, left, up, up_left));
+        }
+    }
+    Ok(out)
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bits: u32,
+    count: u32,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, count: u32) -> Result<u32, String> {
+        while self.count < count {
+            let byte = *self
+                .data
+                .get(self.position)
+                .ok_or_else(|| image_error("compressed data ends early"))?;
+            self.position += 1;
+            self.bits |= u32::from(byte) << self.count;
+            self.count += 8;
+        }
+        let value = self.bits & ((1u64 << count) - 1) as u32;
+        self.bits >>= count;
+        self.count -= count;
+        Ok(value)
+    }
+
+    fn align(&mut self) {
+        self.bits = 0;
+        self.count = 0;
+    }
+}
+
+/// Canonical Huffman table as code length counts and symbols in code order.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[usize::from(length)] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; 16];
+        for bits in 1..16 {
+            offsets[bits] = offsets[bits - 1] + counts[bits - 1];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length > 0 {
+                symbols[usize::from(offsets[usize::from(length)])] = symbol as u16;
+                offsets[usize::from(length)] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for bits in 1..16 {
+            code |= reader.bits(1)? as i32;
+            let count = i32::from(self.counts[bits]);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(image_error("invalid Huffman code"))
+    }
+}
+
+fn inflate_zlib(data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() < 6
+        || data[0] & 0x0f != 8
+        || (u16::from(data[0]) << 8 | u16::from(data[1])) % 31 != 0
+    {
+        return Err(image_error("invalid zlib header"));
+    }
+    if data[1] & 0x20 != 0 {
+        return Err(image_error("preset zlib dictionaries are not supported"));
+    }
+
+    let mut reader = BitReader {
+        data: &data[2..],
+        position: 0,
+        bits: 0,
+        count: 0,
+    };
+    let mut out = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let start = reader.position;
+                let header = reader
+                    .data
+                    .get(start..start + 4)
+                    .ok_or_else(|| image_error("compressed data ends early"))?;
+                let length = usize::from(u16::from_le_bytes([header[0], header[1]]));
+                let block = reader
+                    .data
+                    .get(start + 4..start + 4 + length)
+                    .ok_or_else(|| image_error("compressed data ends early"))?;
+                out.extend_from_slice(block);
+                reader.position = start + 4 + length;
+            }
+            1 => {
+                let mut lengths = [0u8; 288 + 30];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..288].fill(8);
+                lengths[288..].fill(5);
+                let literals = Huffman::new(&lengths[..288]);
+                let distances = Huffman::new(&lengths[288..]);
+                inflate_block(&mut reader, &literals, &distances, &mut out)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_tables(&mut reader)?;
+                inflate_block(&mut reader, &literals, &distances, &mut out)?;
+            }
+            _ => return Err(image_error("invalid deflate block type")),
+        }
+        if last {
+            break;
+        }
+    }
+
+    reader.align();
+    let checksum = reader
+        .data
+        .get(reader.position..reader.position + 4)
+        .ok_or_else(|| image_error("missing zlib checksum"))?;
+    if adler32(&out).to_be_bytes() != checksum {
+        return Err(image_error("zlib checksum mismatch"));
+    }
+    Ok(out)
+}
+
+fn dynamic_tables(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    const ORDER: [usize; 19] = [
+        16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+    ];
+
+    let literal_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_count = reader.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &index in &ORDER[..code_count] {
+        code_lengths[index] = reader.bits(3)? as u8;
+    }
+    let codes = Huffman::new(&code_lengths);
+
+    let mut lengths = vec![0u8; literal_count + distance_count];
+    let mut i = 0;
+    while i < lengths.len() {
+        let symbol = codes.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous = *i
+                    .checked_sub(1)
+                    .and_then(|p| lengths.get(p))
+                    .ok_or_else(|| image_error("repeat with no previous length"))?;
+                (previous, 3 + reader.bits(2)? as usize)
+            }
+            17 => (0, 3 + reader.bits(3)? as usize),
+            _ => (0, 11 + reader.bits(7)? as usize),
+        };
+        if i + repeat > lengths.len() {
+            return Err(image_error("too many code lengths"));
+        }
+        lengths[i..i + repeat].fill(value);
+        i += repeat;
+    }
+
+    Ok((
+        Huffman::new(&lengths[..literal_count]),
+        Huffman::new(&lengths[literal_count..]),
+    ))
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    literals: &Huffman,
+    distances: &Huffman,
+    out: &mut Vec<u8>,
+) -> Result<(), String> {
+    loop {
+        let symbol = usize::from(literals.decode(reader)?);
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let code = symbol - 257;
+                let length = usize::from(LENGTH_BASE[code])
+                    + reader.bits(u32::from(LENGTH_EXTRA[code]))? as usize;
+                let code = usize::from(distances.decode(reader)?);
+                if code >= DISTANCE_BASE.len() {
+                    return Err(image_error("invalid distance code"));
+                }
+                let distance = usize::from(DISTANCE_BASE[code])
+                    + reader.bits(u32::from(DISTANCE_EXTRA[code]))? as usize;
+                if distance > out.len() {
+                    return Err(image_error("distance reaches before the start"));
+                }
+                let start = out.len() - distance;
+                for j in 0..length {
+                    out.push(out[start + j]);
+                }
+            }
+            _ => return Err(image_error("invalid literal/length code")),
+        }
+    }
+}
+
+fn image_error(message: &str) -> String {
+    message.to_string()
+}
+
+/// Compares two images of the same size, returning the number of pixels
+/// with a channel further than `tolerance` apart and a diff image marking
+/// them in red over a darkened copy of `expected`.
+pub fn diff_image(actual: &Image, expected: &Image, tolerance: u8) -> (usize, Image) {
+    let mut pixels = Vec::with_capacity(actual.pixels.len());
+    let mut mismatched = 0;
+    for (a, e) in actual
+        .pixels
+        .chunks_exact(4)
+        .zip(expected.pixels.chunks_exact(4))
+    {
+        if a.iter().zip(e).any(|(a, e)| a.abs_diff(*e) > tolerance) {
+            mismatched += 1;
+            pixels.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            pixels.extend_from_slice(&[e[0] / 4, e[1] / 4, e[2] / 4, 255]);
+        }
+    }
+    let diff = Image {
+        width: actual.width,
+        height: actual.height,
+        pixels,
+    };
+    (mismatched, diff)
+}
@@ -0,0 +1,260 @@
+use floating_particles::{BoundaryMode, ConnectionFormat, ParticleSystem};
+
+// 与最初的逐对遍历实现保持一致，作为网格加速结果的参照
+fn brute_force(system: &ParticleSystem) -> Vec<f64> {
+    let particles = system.particles();
+    let (width, height) = (system.width(), system.height());
+    let distance_limit = system.connection_distance();
+    let (mouse_x, mouse_y) = (system.mouse_x(), system.mouse_y());
+    let mouse_radius = system.mouse_radius();
+    let mut connections = Vec::new();
+
+    for i in 0..particles.len() {
+        let p1 = particles[i];
+        for p2 in &particles[i + 1..] {
+            let dx = (p1.x - p2.x).abs();
+            let dy = (p1.y - p2.y).abs();
+            if dx > width / 2.0 || dy > height / 2.0 {
+                continue;
+            }
+
+            let distance = (dx * dx + dy * dy).sqrt();
+            if distance < distance_limit {
+                let mut opacity = 1.0 - distance / distance_limit;
+                let d1 = ((p1.x - mouse_x).powi(2) + (p1.y - mouse_y).powi(2)).sqrt();
+                let d2 = ((p2.x - mouse_x).powi(2) + (p2.y - mouse_y).powi(2)).sqrt();
+                if d1 < mouse_radius || d2 < mouse_radius {
+                    opacity *= 1.3;
+                }
+                connections.extend_from_slice(&[p1.x, p1.y, p2.x, p2.y, opacity]);
+            }
+        }
+    }
+
+    connections
+}
+
+#[test]
+fn grid_matches_brute_force() {
+    for seed in 0..8 {
+        let mut system = ParticleSystem::with_seed(
+            640.0 + seed as f64 * 50.0,
+            480.0,
+            150 + seed as usize * 25,
+            60.0 + seed as f64 * 15.0,
+            seed,
+        );
+        system.update_mouse_position(320.0, 240.0);
+        for _ in 0..30 {
+            system.update();
+        }
+
+        assert_eq!(
+            system.connection_data(),
+            brute_force(&system),
+            "seed {seed}"
+        );
+    }
+}
+
+#[test]
+fn connection_distance_larger_than_the_screen() {
+    let mut system = ParticleSystem::with_seed(300.0, 200.0, 60, 1000.0, 4);
+    system.update();
+    assert_eq!(system.connection_data(), brute_force(&system));
+}
+
+#[test]
+fn zero_connection_distance_draws_nothing() {
+    let mut system = ParticleSystem::with_seed(300.0, 200.0, 60, 100.0, 4);
+    system.set_connection_distance(0.0).unwrap();
+    assert!(system.connection_data().is_empty());
+}
+
+#[test]
+fn wrap_splits_lines_that_cross_an_edge() {
+    let mut system = ParticleSystem::with_seed(400.0, 300.0, 300, 60.0, 21);
+    system.set_boundary_mode(BoundaryMode::Wrap);
+    for _ in 0..600 {
+        system.update();
+    }
+
+    let connections = system.connection_data();
+    let outside = |x: f64, y: f64| !(0.0..=400.0).contains(&x) || !(0.0..=300.0).contains(&y);
+    let mut crossing = 0;
+    for line in connections.chunks(5) {
+        let length = ((line[2] - line[0]).powi(2) + (line[3] - line[1]).powi(2)).sqrt();
+        assert!(length < 60.0);
+        if outside(line[2], line[3]) {
+            crossing += 1;
+        }
+    }
+    assert!(crossing > 0);
+}
+
+#[test]
+fn frame_buffer_matches_connection_data() {
+    let mut system = ParticleSystem::with_seed(500.0, 400.0, 200, 80.0, 8);
+    system.update_mouse_position(250.0, 200.0);
+    system.update();
+    system.prepare_frame(false);
+
+    let expected: Vec<f32> = system.connection_data().iter().map(|&v| v as f32).collect();
+    assert_eq!(system.connection_buffer(), expected.as_slice());
+
+    let particles: Vec<f32> = system.particle_data().iter().map(|&v| v as f32).collect();
+    assert_eq!(system.particle_buffer(), particles.as_slice());
+
+    let mouse: Vec<f32> = system
+        .mouse_connection_data()
+        .iter()
+        .map(|&v| v as f32)
+        .collect();
+    assert_eq!(system.mouse_connection_buffer(), mouse.as_slice());
+}
+
+fn star() -> ParticleSystem {
+    // 中心粒子周围按距离依次排开五个粒子，彼此之间互不相连
+    let mut system = ParticleSystem::with_seed(1000.0, 1000.0, 0, 100.0, 1);
+    system.add_particle(500.0, 500.0).unwrap();
+    for (i, distance) in [99.0, 92.0, 96.0, 94.0, 98.0].into_iter().enumerate() {
+        let angle = i as f64 * std::f64::consts::TAU / 5.0;
+        system
+            .add_particle(
+                500.0 + angle.cos() * distance,
+                500.0 + angle.sin() * distance,
+            )
+            .unwrap();
+    }
+    system
+}
+
+#[test]
+fn degree_limit_keeps_the_nearest_connections() {
+    let mut system = star();
+    assert_eq!(system.connection_data().len(), 5 * 5);
+
+    system.set_max_connections_per_particle(2);
+    let connections = system.connection_data();
+    assert_eq!(connections.len(), 2 * 5);
+    let mut ends: Vec<f64> = connections
+        .chunks(5)
+        .map(|line| ((line[2] - 500.0).powi(2) + (line[3] - 500.0).powi(2)).sqrt())
+        .collect();
+    ends.sort_by(f64::total_cmp);
+    assert!((ends[0] - 92.0).abs() < 1e-9 && (ends[1] - 94.0).abs() < 1e-9);
+
+    let stats = system.connection_stats();
+    assert_eq!(stats.candidates, 5);
+    assert_eq!(stats.dropped_by_degree, 3);
+    assert_eq!(stats.drawn, 2);
+}
+
+#[test]
+fn degree_limit_holds_for_every_particle() {
+    let mut system = ParticleSystem::with_seed(400.0, 400.0, 300, 80.0, 3);
+    system.set_max_connections_per_particle(3);
+    system.update();
+
+    let mut degree = vec![0; system.particle_count()];
+    let particles = system.particles();
+    for line in system.connection_data().chunks(5) {
+        let index = |x: f64, y: f64| particles.iter().position(|p| p.x == x && p.y == y).unwrap();
+        degree[index(line[0], line[1])] += 1;
+        degree[index(line[2], line[3])] += 1;
+    }
+    assert!(degree.iter().all(|&d| d <= 3));
+}
+
+#[test]
+fn line_budget_keeps_the_most_opaque_lines_in_order() {
+    let mut system = ParticleSystem::with_seed(600.0, 600.0, 250, 90.0, 5);
+    system.update();
+    let all = system.connection_data();
+    let total = all.len() / 5;
+
+    system.set_max_connections(40);
+    let limited = system.connection_data();
+    assert_eq!(limited.len(), 40 * 5);
+
+    let mut opacities: Vec<f64> = all.chunks(5).map(|line| line[4]).collect();
+    opacities.sort_by(|a, b| b.total_cmp(a));
+    let threshold = opacities[39];
+    assert!(limited.chunks(5).all(|line| line[4] >= threshold));
+
+    // 保留下来的线保持原有的相对顺序
+    let mut remaining = all.chunks(5);
+    for line in limited.chunks(5) {
+        assert!(remaining.any(|candidate| candidate == line));
+    }
+
+    let stats = system.connection_stats();
+    assert_eq!(stats.candidates, total);
+    assert_eq!(stats.dropped_by_budget, total - 40);
+    assert_eq!(stats.drawn, 40);
+
+    system.prepare_frame(false);
+    assert_eq!(system.connection_buffer().len(), 40 * 5);
+}
+
+#[test]
+fn index_pairs_match_segment_output() {
+    let mut system = ParticleSystem::with_seed(500.0, 400.0, 200, 80.0, 13);
+    system.update_mouse_position(250.0, 200.0);
+    system.update();
+
+    let segments = system.connection_data();
+    let (indices, opacities) = system.connection_indices();
+    assert_eq!(indices.len(), opacities.len() * 2);
+    assert_eq!(opacities.len() * 5, segments.len());
+
+    let particles = system.particles();
+    for ((pair, &opacity), line) in indices.chunks(2).zip(&opacities).zip(segments.chunks(5)) {
+        let (a, b) = (particles[pair[0] as usize], particles[pair[1] as usize]);
+        assert_eq!([a.x, a.y, b.x, b.y], line[..4]);
+        assert_eq!(opacity, line[4] as f32);
+    }
+}
+
+#[test]
+fn indexed_frame_refers_to_the_particle_buffer() {
+    let mut system = ParticleSystem::with_seed(500.0, 400.0, 150, 80.0, 14);
+    system.set_connection_format(ConnectionFormat::Indexed);
+    system.update();
+    system.prepare_frame(true);
+
+    assert!(system.connection_buffer().is_empty());
+    let (indices, opacities) = system.connection_indices();
+    assert_eq!(system.connection_index_buffer(), indices.as_slice());
+    assert_eq!(system.connection_opacity_buffer(), opacities.as_slice());
+
+    let count = system.particle_buffer().len() / 3;
+    assert!(indices.iter().all(|&i| (i as usize) < count));
+}
+
+#[test]
+fn indexed_frames_draw_lines_across_wrapped_edges_as_segments() {
+    let mut system = ParticleSystem::with_seed(400.0, 300.0, 0, 60.0, 1);
+    system.set_boundary_mode(BoundaryMode::Wrap);
+    system.add_particle(5.0, 150.0).unwrap();
+    system.add_particle(395.0, 150.0).unwrap();
+    system.add_particle(40.0, 150.0).unwrap();
+
+    let (indices, opacities) = system.connection_indices();
+    assert_eq!(indices, vec![0, 2]);
+    assert_eq!(opacities.len(), 1);
+
+    system.set_connection_format(ConnectionFormat::Indexed);
+    system.prepare_frame(false);
+    assert_eq!(system.connection_index_buffer(), [0, 2]);
+    // 0-1 与 1-2 跨越左右边缘，各拆成两段；0-2 由索引绘制
+    let data = system.connection_data();
+    let lines: Vec<&[f64]> = data.chunks(5).collect();
+    let expected: Vec<f32> = [lines[0], lines[1], lines[3], lines[4]]
+        .concat()
+        .iter()
+        .map(|&v| v as f32)
+        .collect();
+    assert_eq!(system.connection_buffer(), expected.as_slice());
+    assert_eq!(system.connection_stats().drawn, 3);
+}
@@ -0,0 +1,238 @@
+use floating_particles::{EmitterShape, ParticleError, ParticleField, ParticleSystem};
+
+fn empty_system() -> ParticleSystem {
+    ParticleSystem::with_seed(800.0, 600.0, 0, 100.0, 21)
+}
+
+#[test]
+fn rate_spawns_particles_per_second_of_simulated_time() {
+    let mut system = empty_system();
+    let id = system
+        .add_emitter(EmitterShape::Point, 400.0, 300.0, 0.0, 0.0)
+        .unwrap();
+    system.set_emitter_rate(id, 30.0).unwrap();
+    system
+        .set_emitter_lifetime(id, f64::INFINITY, 0.0, 0.0)
+        .unwrap();
+
+    for _ in 0..60 {
+        system.update();
+    }
+    assert_eq!(system.particle_count(), 30);
+}
+
+#[test]
+fn bursts_fire_on_the_first_step_and_on_trigger() {
+    let mut system = empty_system();
+    let id = system
+        .add_emitter(EmitterShape::Rectangle, 100.0, 100.0, 200.0, 150.0)
+        .unwrap();
+    system.set_emitter_rate(id, 0.0).unwrap();
+    system.set_emitter_burst(id, 25);
+
+    system.update();
+    assert_eq!(system.particle_count(), 25);
+    for particle in system.particles() {
+        assert!((100.0..=200.0).contains(&particle.prev_x));
+        assert!((100.0..=150.0).contains(&particle.prev_y));
+    }
+
+    system.update();
+    assert_eq!(system.particle_count(), 25);
+    system.trigger_emitter(id);
+    system.update();
+    assert_eq!(system.particle_count(), 50);
+}
+
+#[test]
+fn particles_expire_after_their_lifetime() {
+    let mut system = empty_system();
+    let id = system
+        .add_emitter(EmitterShape::Line, 0.0, 300.0, 800.0, 300.0)
+        .unwrap();
+    system.set_emitter_rate(id, 0.0).unwrap();
+    system.set_emitter_burst(id, 10);
+    system
+        .set_emitter_lifetime(id, 500.0, 100.0, 200.0)
+        .unwrap();
+
+    system.update();
+    assert_eq!(system.particle_count(), 10);
+    let ids = system.particle_ids();
+
+    // 500ms 约为 30 步
+    for _ in 0..29 {
+        system.update();
+    }
+    assert_eq!(system.particle_count(), 10);
+    system.update();
+    assert_eq!(system.particle_count(), 0);
+    assert!(ids.iter().all(|&id| !system.contains_particle(id)));
+}
+
+#[test]
+fn velocity_cone_limits_launch_direction() {
+    let mut system = empty_system();
+    let id = system
+        .add_emitter(EmitterShape::Point, 400.0, 300.0, 0.0, 0.0)
+        .unwrap();
+    system.set_emitter_rate(id, 0.0).unwrap();
+    system.set_emitter_burst(id, 200);
+    system
+        .set_emitter_velocity(id, std::f64::consts::FRAC_PI_2, 0.3, 1.0, 2.0)
+        .unwrap();
+    system.update();
+
+    for particle in system.particles() {
+        let angle = particle.base_vy.atan2(particle.base_vx);
+        let speed = particle.base_vx.hypot(particle.base_vy);
+        assert!((angle - std::f64::consts::FRAC_PI_2).abs() <= 0.3 + 1e-9);
+        assert!((1.0 - 1e-9..=2.0 + 1e-9).contains(&speed));
+    }
+}
+
+#[test]
+fn mouse_trail_only_emits_while_the_mouse_moves() {
+    let mut system = empty_system();
+    let id = system
+        .add_emitter(EmitterShape::MouseTrail, 0.0, 0.0, 0.0, 0.0)
+        .unwrap();
+    system.set_emitter_rate(id, 120.0).unwrap();
+
+    for _ in 0..10 {
+        system.update();
+    }
+    assert_eq!(system.particle_count(), 0);
+
+    system.update_mouse_position(100.0, 100.0);
+    system.update();
+    system.update();
+    assert_eq!(system.particle_count(), 0);
+
+    for step in 0..10 {
+        system.update_mouse_position(100.0 + step as f64 * 10.0, 100.0);
+        system.update();
+    }
+    assert!(system.particle_count() > 0);
+}
+
+#[test]
+fn age_field_reports_age_and_fade() {
+    let mut system = empty_system();
+    let id = system
+        .add_emitter(EmitterShape::Point, 400.0, 300.0, 0.0, 0.0)
+        .unwrap();
+    system.set_emitter_rate(id, 0.0).unwrap();
+    system.set_emitter_burst(id, 1);
+    system
+        .set_emitter_lifetime(id, 1000.0, 100.0, 100.0)
+        .unwrap();
+    system.set_particle_fields(ParticleField::Age as u32);
+    assert_eq!(system.particle_stride(), 5);
+
+    system.update();
+    let data = system.particle_data();
+    assert_eq!(data.len(), 5);
+    assert_eq!(data[3], 0.0);
+    assert_eq!(data[4], 0.0);
+
+    for _ in 0..30 {
+        system.update();
+    }
+    let data = system.particle_data();
+    assert!((data[3] - 500.0).abs() < 1e-9);
+    assert_eq!(data[4], 1.0);
+
+    system.prepare_frame(false);
+    assert_eq!(system.particle_buffer().len(), 5);
+}
+
+#[test]
+fn non_finite_emitter_inputs_are_rejected() {
+    let mut system = empty_system();
+    assert!(system
+        .add_emitter(EmitterShape::Line, 0.0, f64::NAN, 10.0, 10.0)
+        .is_err());
+    let id = system
+        .add_emitter(EmitterShape::Point, 400.0, 300.0, 0.0, 0.0)
+        .unwrap();
+
+    assert!(matches!(
+        system.set_emitter_velocity(id, 0.0, 0.5, 0.2, f64::INFINITY),
+        Err(ParticleError::InvalidRange { name: "speed", .. })
+    ));
+    assert!(system
+        .set_emitter_velocity(id, f64::NAN, 0.5, 0.2, 1.0)
+        .is_err());
+    assert!(system
+        .set_emitter_velocity(id, 0.0, f64::NAN, 0.2, 1.0)
+        .is_err());
+    assert!(system
+        .set_emitter_velocity(id, 0.0, 0.5, -1.0, 1.0)
+        .is_err());
+    assert!(system.set_emitter_velocity(id, 0.0, 0.5, 2.0, 1.0).is_err());
+    assert!(system
+        .move_emitter(id, f64::INFINITY, 0.0, 0.0, 0.0)
+        .is_err());
+    assert_eq!(system.move_emitter(id + 1, 1.0, 1.0, 0.0, 0.0), Ok(false));
+
+    for _ in 0..20 {
+        system.update();
+    }
+    assert!(system.particle_count() > 0);
+    assert!(system.particle_data().iter().all(|v| v.is_finite()));
+}
+
+#[test]
+fn invalid_rates_and_lifetimes_are_rejected() {
+    let mut system = empty_system();
+    let id = system
+        .add_emitter(EmitterShape::Point, 400.0, 300.0, 0.0, 0.0)
+        .unwrap();
+    system.set_emitter_rate(id, 0.0).unwrap();
+    assert!(matches!(
+        system.set_emitter_rate(id, f64::NAN),
+        Err(ParticleError::InvalidValue { name: "rate", .. })
+    ));
+    assert!(system.set_emitter_rate(id, -5.0).is_err());
+    assert!(system.set_emitter_rate(id, f64::INFINITY).is_err());
+    assert_eq!(system.set_emitter_rate(id + 1, 5.0), Ok(false));
+
+    assert!(matches!(
+        system.set_emitter_lifetime(id, f64::NAN, 0.0, 0.0),
+        Err(ParticleError::InvalidValue {
+            name: "lifetime",
+            ..
+        })
+    ));
+    assert!(system.set_emitter_lifetime(id, -1.0, 0.0, 0.0).is_err());
+    assert!(system
+        .set_emitter_lifetime(id, 500.0, f64::NAN, 0.0)
+        .is_err());
+    assert!(system.set_emitter_lifetime(id, 500.0, 0.0, -1.0).is_err());
+    assert_eq!(
+        system.set_emitter_lifetime(id, f64::INFINITY, 0.0, 0.0),
+        Ok(true)
+    );
+    assert_eq!(
+        system.set_emitter_lifetime(id + 1, 500.0, 0.0, 0.0),
+        Ok(false)
+    );
+
+    // 被拒绝的速率不会生效
+    for _ in 0..60 {
+        system.update();
+    }
+    assert_eq!(system.particle_count(), 0);
+}
+
+#[test]
+fn default_layout_is_unchanged() {
+    let mut system = ParticleSystem::with_seed(800.0, 600.0, 40, 100.0, 4);
+    assert_eq!(system.particle_stride(), 3);
+    system.update();
+    assert_eq!(system.particle_data().len(), 120);
+    for particle in system.particles() {
+        assert_eq!(particle.fade(), 1.0);
+    }
+}
@@ -0,0 +1,175 @@
+//! Golden-frame regression tests: seeded systems are stepped along a
+//! scripted mouse path, rendered, and compared with the reference PNGs in
+//! `tests/golden`.
+//!
+//! Run with `UPDATE_GOLDEN=1` to rewrite the references after an intended
+//! change. On a mismatch the rendered frame and a diff image are written to
+//! `target/golden`.
+
+mod common;
+
+use common::png::{decode_png, diff_image, encode_png, Image};
+use floating_particles::{
+    BoundaryMode, ColorMode, EmitterShape, ParticleConfig, ParticleSystem, Renderer,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const WIDTH: u32 = 160;
+const HEIGHT: u32 = 120;
+// 单通道允许的误差（吸收不同平台上三角函数的细微差异），以及超出误差的像素比例上限
+const CHANNEL_TOLERANCE: u8 = 8;
+const MAX_MISMATCH_RATIO: f64 = 0.002;
+
+/// Mouse keyframes `(step, x, y)`, interpolated linearly between keys and
+/// held after the last one.
+type MousePath = &'static [(usize, f64, f64)];
+
+fn mouse_at(path: MousePath, step: usize) -> Option<(f64, f64)> {
+    let (first, rest) = path.split_first()?;
+    if step <= first.0 {
+        return Some((first.1, first.2));
+    }
+    let mut previous = first;
+    for key in rest {
+        if step <= key.0 {
+            let t = (step - previous.0) as f64 / (key.0 - previous.0) as f64;
+            return Some((
+                previous.1 + (key.1 - previous.1) * t,
+                previous.2 + (key.2 - previous.2) * t,
+            ));
+        }
+        previous = key;
+    }
+    Some((previous.1, previous.2))
+}
+
+fn run(system: &mut ParticleSystem, steps: usize, path: MousePath) -> Image {
+    for step in 0..steps {
+        if let Some((x, y)) = mouse_at(path, step) {
+            system.update_mouse_position(x, y);
+        }
+        system.update();
+    }
+
+    let mut renderer = Renderer::new(WIDTH, HEIGHT).unwrap();
+    renderer.set_background(0.04, 0.05, 0.1, 1.0).unwrap();
+    renderer.render(system);
+    Image {
+        width: WIDTH,
+        height: HEIGHT,
+        pixels: renderer.pixels().to_vec(),
+    }
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn output_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("target/golden")
+}
+
+fn assert_golden(name: &str, actual: &Image) {
+    let reference = golden_dir().join(format!("{name}.png"));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(golden_dir()).unwrap();
+        fs::write(
+            &reference,
+            encode_png(actual.width, actual.height, &actual.pixels).unwrap(),
+        )
+        .unwrap();
+        return;
+    }
+
+    let bytes = fs::read(&reference).unwrap_or_else(|error| {
+        panic!(
+            "cannot read {}: {error}; run with UPDATE_GOLDEN=1 to create it",
+            reference.display()
+        )
+    });
+    let expected = decode_png(&bytes).unwrap();
+    assert_eq!(
+        (expected.width, expected.height),
+        (actual.width, actual.height),
+        "{name}: size changed"
+    );
+
+    let (mismatched, diff) = diff_image(actual, &expected, CHANNEL_TOLERANCE);
+    let total = (actual.width * actual.height) as usize;
+    if mismatched as f64 > total as f64 * MAX_MISMATCH_RATIO {
+        fs::create_dir_all(output_dir()).unwrap();
+        let actual_path = output_dir().join(format!("{name}.actual.png"));
+        let diff_path = output_dir().join(format!("{name}.diff.png"));
+        fs::write(
+            &actual_path,
+            encode_png(actual.width, actual.height, &actual.pixels).unwrap(),
+        )
+        .unwrap();
+        fs::write(
+            &diff_path,
+            encode_png(diff.width, diff.height, &diff.pixels).unwrap(),
+        )
+        .unwrap();
+        panic!(
+            "{name}: {mismatched} of {total} pixels differ from {}; see {} and {}",
+            reference.display(),
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+#[test]
+fn drift_without_mouse() {
+    let mut system = ParticleSystem::with_seed(320.0, 240.0, 80, 60.0, 1);
+    let frame = run(&mut system, 120, &[]);
+    assert_golden("drift", &frame);
+}
+
+#[test]
+fn mouse_sweep_attracts_and_connects() {
+    let mut system = ParticleSystem::with_seed(320.0, 240.0, 100, 60.0, 2);
+    system.set_mouse_radius(60.0).unwrap();
+    let path = &[(0, 20.0, 30.0), (60, 300.0, 200.0), (90, 160.0, 120.0)];
+    let frame = run(&mut system, 150, path);
+    assert_golden("mouse_sweep", &frame);
+}
+
+#[test]
+fn repelling_mouse_with_bounce() {
+    let mut system = ParticleSystem::with_seed(320.0, 240.0, 120, 50.0, 3);
+    system.set_mouse_radius(50.0).unwrap();
+    system.set_mouse_force(-2.0).unwrap();
+    system.set_boundary_mode(BoundaryMode::Bounce);
+    let path = &[(0, 160.0, 20.0), (100, 160.0, 220.0)];
+    let frame = run(&mut system, 100, path);
+    assert_golden("repel_bounce", &frame);
+}
+
+#[test]
+fn constellation_preset() {
+    let mut config = ParticleConfig::preset("constellation").unwrap();
+    config.width = 320.0;
+    config.height = 240.0;
+    config.particle_count = 90;
+    config.connection_distance = 60.0;
+    config.mouse_radius = 70.0;
+    config.seed = Some(4);
+    let mut system = ParticleSystem::from_config(&config).unwrap();
+    let path = &[(0, 80.0, 60.0), (120, 240.0, 180.0)];
+    let frame = run(&mut system, 120, path);
+    assert_golden("constellation", &frame);
+}
+
+#[test]
+fn emitter_with_speed_colours() {
+    let mut system = ParticleSystem::with_seed(320.0, 240.0, 30, 50.0, 5);
+    system.set_color_mode(ColorMode::Speed);
+    let id = system
+        .add_emitter(EmitterShape::Point, 160.0, 120.0, 0.0, 0.0)
+        .unwrap();
+    system.set_emitter_rate(id, 30.0).unwrap();
+    let frame = run(&mut system, 90, &[(0, 100.0, 100.0)]);
+    assert_golden("emitter_speed", &frame);
+}
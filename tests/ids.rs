@@ -0,0 +1,135 @@
+use floating_particles::{BoundaryMode, ParticleError, ParticleSystem};
+
+#[test]
+fn ids_survive_removal_of_other_particles() {
+    let mut system = ParticleSystem::with_seed(800.0, 600.0, 50, 100.0, 3);
+    let ids = system.particle_ids();
+    let kept = ids[49];
+    let before = system.particle(kept).unwrap();
+
+    assert!(system.remove_particle(ids[0]));
+    assert_eq!(system.particle_count(), 49);
+    assert!(!system.contains_particle(ids[0]));
+
+    // 最后一个粒子被换到被删除的位置，但 ID 仍指向它
+    assert_eq!(system.particle_index(kept), Some(0));
+    let after = system.particle(kept).unwrap();
+    assert_eq!((after.x, after.y), (before.x, before.y));
+}
+
+#[test]
+fn removed_ids_stay_invalid_after_their_slot_is_reused() {
+    let mut system = ParticleSystem::with_seed(800.0, 600.0, 10, 100.0, 3);
+    let removed = system.particle_ids()[4];
+    assert!(system.remove_particle(removed));
+    assert!(!system.remove_particle(removed));
+
+    let added = system.add_particle(10.0, 20.0).unwrap();
+    assert_ne!(added, removed);
+    assert!(!system.contains_particle(removed));
+    let particle = system.particle(added).unwrap();
+    assert_eq!((particle.x, particle.y), (10.0, 20.0));
+}
+
+#[test]
+fn set_particle_count_grows_and_trims_the_newest() {
+    let mut system = ParticleSystem::with_seed(800.0, 600.0, 20, 100.0, 5);
+    let original = system.particle_ids();
+
+    system.set_particle_count(35).unwrap();
+    assert_eq!(system.particle_count(), 35);
+    assert_eq!(&system.particle_ids()[..20], original.as_slice());
+
+    system.set_particle_count(10).unwrap();
+    assert_eq!(system.particle_ids(), original[..10]);
+
+    assert_eq!(
+        system.set_particle_count(usize::MAX),
+        Err(ParticleError::TooManyParticles)
+    );
+}
+
+#[test]
+fn id_buffer_matches_particle_order() {
+    let mut system = ParticleSystem::with_seed(800.0, 600.0, 30, 100.0, 8);
+    system.remove_particle(system.particle_ids()[3]);
+    system
+        .add_particle_with_velocity(400.0, 300.0, 1.0, 0.0)
+        .unwrap();
+    system.update();
+    system.prepare_frame(false);
+
+    assert_eq!(system.id_buffer(), system.particle_ids().as_slice());
+    assert_eq!(system.id_buffer().len() * 3, system.particle_buffer().len());
+}
+
+#[test]
+fn connections_see_added_particles_immediately() {
+    let mut system = ParticleSystem::with_seed(1000.0, 1000.0, 0, 100.0, 1);
+    system.add_particle(500.0, 500.0).unwrap();
+    system.add_particle(540.0, 500.0).unwrap();
+
+    assert_eq!(system.connection_data().len(), 5);
+}
+
+#[test]
+fn removal_keeps_mouse_connections_in_bounds() {
+    let mut system = ParticleSystem::with_seed(400.0, 400.0, 100, 50.0, 9);
+    system.update_mouse_position(200.0, 200.0);
+    system.update();
+
+    for id in system.particle_ids().into_iter().step_by(2) {
+        system.remove_particle(id);
+    }
+    system.mouse_connection_data();
+    system.prepare_frame(true);
+}
+
+#[test]
+fn absorbed_particles_get_new_ids() {
+    let mut system = ParticleSystem::with_seed(100.0, 100.0, 0, 10.0, 2);
+    system.set_boundary_mode(BoundaryMode::Absorb);
+    let id = system
+        .add_particle_with_velocity(99.5, 50.0, 1.0, 0.0)
+        .unwrap();
+    system.update();
+
+    assert!(!system.contains_particle(id));
+    assert_eq!(system.particle_count(), 1);
+    assert!(system.contains_particle(system.particle_ids()[0]));
+}
+
+#[test]
+fn non_finite_positions_are_rejected() {
+    let mut system = ParticleSystem::with_seed(100.0, 100.0, 0, 10.0, 2);
+    assert!(matches!(
+        system.add_particle(f64::NAN, 0.0),
+        Err(ParticleError::InvalidValue { name: "x", .. })
+    ));
+    assert_eq!(system.particle_count(), 0);
+}
+
+#[test]
+fn exhausted_slots_are_retired_instead_of_wrapping() {
+    let mut system = ParticleSystem::with_seed(100.0, 100.0, 1, 10.0, 2);
+    let first = system.particle_ids()[0];
+    assert!(system.remove_particle(first));
+
+    let mut seen = std::collections::HashSet::from([first]);
+    for _ in 0..5000 {
+        let id = system.add_particle(10.0, 10.0).unwrap();
+        assert!(seen.insert(id), "id {id} was handed out twice");
+        assert!(!system.contains_particle(first));
+        assert!(!system.remove_particle(first));
+        assert!(system.remove_particle(id));
+    }
+
+    // 停用的槽位在快照往返后仍然停用
+    let kept = system.add_particle(20.0, 20.0).unwrap();
+    let mut restored = ParticleSystem::with_seed(10.0, 10.0, 0, 5.0, 0);
+    restored.restore(&system.snapshot()).unwrap();
+    assert!(restored.contains_particle(kept));
+    assert!(!restored.contains_particle(first));
+    let next = restored.add_particle(30.0, 30.0).unwrap();
+    assert!(!seen.contains(&next));
+}
@@ -0,0 +1,115 @@
+use floating_particles::{AttractorKind, Falloff, MotionModel, Particle, ParticleSystem};
+
+/// One particle at the centre of a large canvas, drifting at `(vx, vy)`.
+fn single_particle(model: MotionModel, vx: f64, vy: f64) -> (ParticleSystem, u32) {
+    let mut system = ParticleSystem::with_seed(4000.0, 4000.0, 0, 50.0, 17);
+    system.set_motion_model(model);
+    let id = system
+        .add_particle_with_velocity(2000.0, 2000.0, vx, vy)
+        .unwrap();
+    (system, id)
+}
+
+/// Pulls the particle towards a point to its right for `steps` steps, then
+/// removes the pull.
+fn push(system: &mut ParticleSystem, strength: f64, steps: usize) {
+    let attractor = system
+        .add_attractor(
+            2300.0,
+            2000.0,
+            1000.0,
+            strength,
+            AttractorKind::Attract,
+            Falloff::Constant,
+        )
+        .unwrap();
+    for _ in 0..steps {
+        system.update();
+    }
+    system.remove_attractor(attractor);
+}
+
+fn particle(system: &ParticleSystem, id: u32) -> Particle {
+    system.particle(id).unwrap()
+}
+
+#[test]
+fn momentum_outlasts_the_force() {
+    let (mut inertial, id) = single_particle(MotionModel::Inertial, 0.0, 0.0);
+    let (mut kinematic, _) = single_particle(MotionModel::Kinematic, 0.0, 0.0);
+    push(&mut inertial, 0.2, 10);
+    push(&mut kinematic, 0.2, 10);
+
+    let before = particle(&inertial, id);
+    inertial.update();
+    let after = particle(&inertial, id);
+    assert!(after.vx > 1.0);
+    assert!(after.x > before.x + 1.0);
+
+    let before = particle(&kinematic, id);
+    kinematic.update();
+    let after = particle(&kinematic, id);
+    assert_eq!((after.vx, after.x), (0.0, before.x));
+}
+
+#[test]
+fn damping_decays_velocity_geometrically() {
+    let (mut system, id) = single_particle(MotionModel::Inertial, 2.0, -1.0);
+    system.set_linear_damping(0.1).unwrap();
+    system.set_velocity_relaxation(0.0).unwrap();
+
+    let mut expected = (2.0, -1.0);
+    for _ in 0..30 {
+        system.update();
+        expected = (expected.0 * 0.9, expected.1 * 0.9);
+        let particle = particle(&system, id);
+        assert!((particle.vx - expected.0).abs() < 1e-12);
+        assert!((particle.vy - expected.1).abs() < 1e-12);
+    }
+    assert!(particle(&system, id).vx < 0.1);
+}
+
+#[test]
+fn velocity_relaxes_towards_the_base_drift() {
+    let (mut system, id) = single_particle(MotionModel::Inertial, 0.5, 0.0);
+    system.set_linear_damping(0.0).unwrap();
+    system.set_velocity_relaxation(0.25).unwrap();
+    push(&mut system, 1.0, 1);
+
+    let mut gap = particle(&system, id).vx - 0.5;
+    assert!(gap > 0.5);
+    for _ in 0..40 {
+        system.update();
+        let next = particle(&system, id).vx - 0.5;
+        assert!((next - gap * 0.75).abs() < 1e-12);
+        gap = next;
+    }
+    let particle = particle(&system, id);
+    assert!((particle.vx - particle.base_vx).abs() < 1e-4);
+    assert_eq!(particle.base_vx, 0.5);
+}
+
+#[test]
+fn speed_is_clamped_to_max_speed() {
+    let (mut system, id) = single_particle(MotionModel::Inertial, 0.0, 0.0);
+    system.set_max_speed(1.5).unwrap();
+    system
+        .add_attractor(
+            2300.0,
+            2000.0,
+            1000.0,
+            5.0,
+            AttractorKind::Attract,
+            Falloff::Constant,
+        )
+        .unwrap();
+
+    for _ in 0..20 {
+        let before = particle(&system, id);
+        system.update();
+        let after = particle(&system, id);
+        let speed = after.vx.hypot(after.vy);
+        assert!((speed - 1.5).abs() < 1e-12);
+        assert!(((after.x - before.x) - after.vx).abs() < 1e-12);
+    }
+}
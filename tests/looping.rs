@@ -0,0 +1,137 @@
+use floating_particles::{BoundaryMode, ParticleConfig, ParticleSystem, SvgAnimation, SvgOptions};
+
+fn looping(period_ms: f64) -> ParticleSystem {
+    let mut system = ParticleSystem::with_seed(400.0, 300.0, 60, 80.0, 11);
+    system.set_loop_period_ms(period_ms).unwrap();
+    system
+}
+
+fn bits(system: &ParticleSystem) -> Vec<u64> {
+    system.particle_data().iter().map(|v| v.to_bits()).collect()
+}
+
+fn run(system: &mut ParticleSystem, steps: usize) {
+    for _ in 0..steps {
+        system.update();
+    }
+}
+
+#[test]
+fn state_after_one_period_matches_the_start() {
+    // 1000 ms 为 60 个固定步长
+    let mut system = looping(1000.0);
+    let start = bits(&system);
+    let connections = system.connection_data();
+
+    run(&mut system, 30);
+    assert_ne!(bits(&system), start);
+    assert!((system.loop_progress() - 0.5).abs() < 1e-12);
+
+    run(&mut system, 30);
+    assert_eq!(bits(&system), start);
+    assert_eq!(system.connection_data(), connections);
+    assert_eq!(system.loop_progress(), 0.0);
+
+    run(&mut system, 60);
+    assert_eq!(bits(&system), start);
+}
+
+#[test]
+fn motion_is_smooth_across_the_loop() {
+    let mut system = looping(2000.0);
+    for _ in 0..240 {
+        let before = system.particles().to_vec();
+        system.update();
+        for (old, new) in before.iter().zip(system.particles()) {
+            // 穿过边缘时按环面距离计算
+            let dx = (new.x - old.x).abs();
+            let dy = (new.y - old.y).abs();
+            assert!(dx.min(400.0 - dx) < 4.0 && dy.min(300.0 - dy) < 4.0);
+            assert!(new.x >= 0.0 && new.x < 400.0 && new.y >= 0.0 && new.y < 300.0);
+            assert_eq!(new.prev_x, new.x - new.vx);
+        }
+    }
+}
+
+#[test]
+fn mouse_does_not_disturb_the_loop() {
+    let mut still = looping(1000.0);
+    let mut stirred = looping(1000.0);
+    for step in 0..90 {
+        let t = step as f64 * 0.1;
+        stirred.update_mouse_position(200.0 + t.cos() * 100.0, 150.0 + t.sin() * 80.0);
+        still.update();
+        stirred.update();
+    }
+    assert_eq!(bits(&stirred), bits(&still));
+    assert_eq!(stirred.mouse_connection_data(), Vec::<f64>::new());
+}
+
+#[test]
+fn resizing_starts_a_new_loop() {
+    let mut system = looping(500.0);
+    run(&mut system, 7);
+    system.resize(320.0, 240.0).unwrap();
+    // 缩小后停在边缘上的粒子在环面上落到对边，从下一步开始比较
+    system.update();
+    let start = bits(&system);
+
+    run(&mut system, 30);
+    assert_eq!(bits(&system), start);
+    assert!(system
+        .particles()
+        .iter()
+        .all(|p| p.x < 320.0 && p.y < 240.0));
+}
+
+#[test]
+fn turning_looping_off_resumes_the_physics() {
+    let mut system = ParticleSystem::with_seed(10_000.0, 10_000.0, 20, 100.0, 5);
+    system.set_boundary_mode(BoundaryMode::Bounce);
+    system.set_loop_period_ms(1000.0).unwrap();
+    run(&mut system, 10);
+
+    system.set_loop_period_ms(0.0).unwrap();
+    assert_eq!(system.loop_period_ms(), 0.0);
+    let before = system.particles().to_vec();
+    system.update();
+    for (old, new) in before.iter().zip(system.particles()) {
+        assert_eq!(new.x, old.x + old.base_vx);
+        assert_eq!(new.y, old.y + old.base_vy);
+    }
+}
+
+#[test]
+fn loop_period_is_part_of_the_config() {
+    let mut config = ParticleConfig {
+        loop_period_ms: 4000.0,
+        ..ParticleConfig::default()
+    };
+    config.set_seed(Some(3));
+    let parsed = ParticleConfig::from_json(&config.to_json()).unwrap();
+    assert_eq!(parsed, config);
+
+    let system = ParticleSystem::from_config(&parsed).unwrap();
+    assert_eq!(system.loop_period_ms(), 4000.0);
+    assert_eq!(system.config().loop_period_ms, 4000.0);
+
+    config.loop_period_ms = -1.0;
+    assert!(ParticleSystem::from_config(&config).is_err());
+    assert!(looping(0.0).set_loop_period_ms(f64::NAN).is_err());
+}
+
+#[test]
+fn animated_svg_of_one_period_ends_where_it_starts() {
+    let mut system = looping(2000.0);
+    let mut animation = SvgAnimation::new();
+    animation.set_duration_ms(2000.0).unwrap();
+    let svg = system.to_animated_svg(&SvgOptions::new(), &animation);
+
+    let mut checked = 0;
+    for values in svg.split(r#"type="translate" values=""#).skip(1) {
+        let values: Vec<&str> = values[..values.find('"').unwrap()].split(';').collect();
+        assert_eq!(values.first(), values.last());
+        checked += 1;
+    }
+    assert!(checked > 0);
+}
@@ -0,0 +1,122 @@
+use floating_particles::{Particle, ParticleSystem};
+
+const MOUSE: (f64, f64) = (1000.0, 1000.0);
+
+fn parked_system() -> ParticleSystem {
+    let mut system = ParticleSystem::with_seed(2000.0, 2000.0, 400, 100.0, 11);
+    system.set_mouse_radius(250.0).unwrap();
+    system.update_mouse_position(MOUSE.0, MOUSE.1);
+    system
+}
+
+fn distance_to_mouse(particle: &Particle) -> f64 {
+    (particle.x - MOUSE.0).hypot(particle.y - MOUSE.1)
+}
+
+fn captured_ids(system: &ParticleSystem) -> Vec<u32> {
+    system
+        .particles()
+        .iter()
+        .filter(|particle| particle.is_orbiting)
+        .map(|particle| particle.id)
+        .collect()
+}
+
+#[test]
+fn particles_inside_the_mouse_radius_are_captured() {
+    let mut system = parked_system();
+    let inside: Vec<u32> = system
+        .particles()
+        .iter()
+        .filter(|particle| distance_to_mouse(particle) < 250.0)
+        .map(|particle| particle.id)
+        .collect();
+    assert!(!inside.is_empty());
+
+    system.update();
+    assert_eq!(captured_ids(&system), inside);
+}
+
+#[test]
+fn orbit_entry_is_eased() {
+    let mut system = parked_system();
+    system.update();
+    let ids = captured_ids(&system);
+
+    let mut last = 0.0;
+    for step in 0..60 {
+        let blends: Vec<f64> = ids
+            .iter()
+            .map(|&id| system.particle(id).unwrap().orbit_blend)
+            .collect();
+        if step == 0 {
+            assert!(blends.iter().all(|&blend| (blend - 0.05).abs() < 1e-12));
+        }
+        assert!(blends.iter().all(|&blend| blend > last && blend < 1.0));
+        last = blends[0];
+        system.update();
+    }
+}
+
+#[test]
+fn captured_particles_keep_their_radius_around_the_cursor() {
+    let mut system = parked_system();
+    system.update();
+    let ids = captured_ids(&system);
+
+    for _ in 0..1000 {
+        system.update();
+    }
+    for id in ids {
+        let particle = system.particle(id).unwrap();
+        assert!(particle.is_orbiting);
+        assert!((distance_to_mouse(&particle) - particle.orbit_radius).abs() < 0.5);
+    }
+}
+
+#[test]
+fn released_particles_return_to_their_base_drift() {
+    let mut system = parked_system();
+    for _ in 0..300 {
+        system.update();
+    }
+    let ids = captured_ids(&system);
+
+    system.update_mouse_position(-1.0, -1.0);
+    system.update();
+    let released: Vec<Particle> = ids.iter().map(|&id| system.particle(id).unwrap()).collect();
+    for particle in &released {
+        assert!(!particle.is_orbiting);
+        assert!(particle.orbit_blend > 0.0);
+        // 离开后仍保留大部分轨道速度
+        assert_ne!(
+            (particle.vx, particle.vy),
+            (particle.base_vx, particle.base_vy)
+        );
+    }
+
+    for _ in 0..200 {
+        system.update();
+    }
+    for id in ids {
+        let particle = system.particle(id).unwrap();
+        assert_eq!(particle.orbit_blend, 0.0);
+        assert_eq!(
+            (particle.vx, particle.vy),
+            (particle.base_vx, particle.base_vy)
+        );
+    }
+}
+
+#[test]
+fn orbit_mode_can_be_disabled() {
+    let mut system = parked_system();
+    system.orbit_enabled = false;
+    for _ in 0..100 {
+        system.update();
+    }
+    assert!(system
+        .particles()
+        .iter()
+        .all(|particle| !particle.is_orbiting && particle.orbit_blend == 0.0));
+}
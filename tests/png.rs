@@ -0,0 +1,52 @@
+mod common;
+
+use common::png::{decode_png, encode_png};
+
+fn gradient(width: u32, height: u32) -> Vec<u8> {
+    (0..height)
+        .flat_map(|y| (0..width).flat_map(move |x| [x as u8 * 3, y as u8 * 5, (x ^ y) as u8, 200]))
+        .collect()
+}
+
+#[test]
+fn encoded_images_decode_to_the_same_pixels() {
+    let pixels = gradient(37, 23);
+    let png = encode_png(37, 23, &pixels).unwrap();
+    let image = decode_png(&png).unwrap();
+    assert_eq!((image.width, image.height), (37, 23));
+    assert_eq!(image.pixels, pixels);
+}
+
+#[test]
+fn flat_images_compress_well() {
+    let pixels = [10, 20, 30, 255].repeat(200 * 100);
+    let png = encode_png(200, 100, &pixels).unwrap();
+    assert!(png.len() < 1000, "{} bytes", png.len());
+    assert_eq!(decode_png(&png).unwrap().pixels, pixels);
+}
+
+#[test]
+fn rgb_files_from_other_encoders_are_read() {
+    // 由 zlib 最高压缩级别生成，使用动态霍夫曼编码块
+    let image = decode_png(include_bytes!("fixtures/rgb8.png")).unwrap();
+    assert_eq!((image.width, image.height), (8, 8));
+    assert_eq!(&image.pixels[..8], &[0, 0, 0, 255, 32, 0, 40, 255]);
+    assert_eq!(&image.pixels[image.pixels.len() - 4..], &[224, 224, 0, 255]);
+}
+
+#[test]
+fn corrupt_files_are_rejected() {
+    let mut png = encode_png(4, 4, &gradient(4, 4)).unwrap();
+    assert!(decode_png(&png[..20]).is_err());
+    assert!(decode_png(b"GIF89a").is_err());
+
+    let last = png.len() - 20;
+    png[last] ^= 0xff;
+    assert!(decode_png(&png).unwrap_err().contains("checksum"));
+}
+
+#[test]
+fn pixel_data_must_match_the_size() {
+    assert!(encode_png(4, 4, &gradient(4, 3)).is_err());
+    assert!(encode_png(0, 0, &[]).is_ok());
+}
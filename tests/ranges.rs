@@ -0,0 +1,125 @@
+use floating_particles::{Particle, ParticleError, ParticleSystem};
+use std::collections::HashMap;
+
+fn by_id(system: &ParticleSystem) -> HashMap<u32, Particle> {
+    system
+        .particles()
+        .iter()
+        .map(|particle| (particle.id, *particle))
+        .collect()
+}
+
+#[test]
+fn velocity_range_changes_ease_particles_to_their_new_drift() {
+    let mut system = ParticleSystem::with_seed(100_000.0, 100_000.0, 40, 50.0, 31);
+    system.update_mouse_position(-1.0, -1.0);
+    let before = by_id(&system);
+
+    system.set_velocity_x_range(1.0, 2.0).unwrap();
+    for particle in system.particles() {
+        let old = before[&particle.id];
+        // 保持在旧范围 [-0.4, 0.4] 中的相对位置
+        let expected = 1.0 + (old.base_vx + 0.4) / 0.8;
+        assert!((particle.target_vx - expected).abs() < 1e-12);
+        assert_eq!(particle.base_vx, old.base_vx);
+        assert_eq!(particle.target_vy, old.base_vy);
+    }
+
+    system.update();
+    for particle in system.particles() {
+        let old = before[&particle.id];
+        let eased = old.base_vx + (particle.target_vx - old.base_vx) * 0.05;
+        assert!((particle.base_vx - eased).abs() < 1e-12);
+        // 本步的速度取自缓动前的基础速度
+        assert_eq!(particle.vx, old.base_vx);
+    }
+
+    for _ in 0..300 {
+        system.update();
+    }
+    for particle in system.particles() {
+        assert_eq!(particle.base_vx, particle.target_vx);
+        assert_eq!(particle.vx, particle.target_vx);
+    }
+}
+
+#[test]
+fn bounced_particles_are_remapped_from_their_original_sample() {
+    let mut system = ParticleSystem::with_seed(120.0, 90.0, 60, 30.0, 9);
+    system.update_mouse_position(-1.0, -1.0);
+    let drawn = by_id(&system);
+    for _ in 0..400 {
+        system.update();
+    }
+    let flipped = system
+        .particles()
+        .iter()
+        .filter(|particle| particle.base_vx * drawn[&particle.id].base_vx < 0.0)
+        .count();
+    assert!(flipped > 0);
+
+    system.set_velocity_range(-0.8, 0.8).unwrap();
+    for particle in system.particles() {
+        let sample = drawn[&particle.id];
+        assert!((particle.target_vx.abs() - 2.0 * sample.base_vx.abs()).abs() < 1e-12);
+        assert!((particle.target_vy.abs() - 2.0 * sample.base_vy.abs()).abs() < 1e-12);
+        // 仍沿反弹后的方向运动
+        assert!(particle.target_vx * particle.base_vx >= 0.0);
+        assert!(particle.target_vy * particle.base_vy >= 0.0);
+    }
+}
+
+#[test]
+fn size_range_changes_ease_sizes() {
+    let mut system = ParticleSystem::with_seed(800.0, 600.0, 30, 50.0, 4);
+    let before = by_id(&system);
+    system.set_size_range(4.0, 8.0).unwrap();
+    for particle in system.particles() {
+        let old = before[&particle.id];
+        assert!((particle.target_size - (4.0 + (old.size - 1.0) * 2.0)).abs() < 1e-12);
+        assert_eq!(particle.size, old.size);
+    }
+
+    for _ in 0..200 {
+        system.update();
+    }
+    for particle in system.particles() {
+        assert_eq!(particle.size, particle.target_size);
+    }
+}
+
+#[test]
+fn orbit_ranges_remap_existing_particles() {
+    let mut system = ParticleSystem::with_seed(800.0, 600.0, 30, 50.0, 4);
+    let before = by_id(&system);
+    system.set_orbit_radius_range(10.0, 20.0).unwrap();
+    system.set_orbit_speed_range(0.01, 0.01).unwrap();
+    for particle in system.particles() {
+        let old = before[&particle.id];
+        let expected = 10.0 + (old.orbit_radius - 5.0) / 55.0 * 10.0;
+        assert!((particle.orbit_radius - expected).abs() < 1e-12);
+        assert_eq!(particle.orbit_speed, 0.01);
+    }
+}
+
+#[test]
+fn invalid_ranges_leave_particles_alone() {
+    let mut system = ParticleSystem::with_seed(800.0, 600.0, 30, 50.0, 4);
+    let before = system.particles().to_vec();
+    assert!(matches!(
+        system.set_velocity_x_range(1.0, -1.0),
+        Err(ParticleError::InvalidRange {
+            name: "velocity_x_range",
+            ..
+        })
+    ));
+    assert!(system.set_velocity_range(0.0, f64::NAN).is_err());
+    assert!(system.set_size_range(-1.0, 2.0).is_err());
+    assert_eq!(system.velocity_x_range(), vec![-0.4, 0.4]);
+    for (old, new) in before.iter().zip(system.particles()) {
+        assert_eq!(
+            (new.target_vx, new.target_vy, new.target_size),
+            (old.target_vx, old.target_vy, old.target_size)
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use floating_particles::{ParticleError, ParticleSystem, Recording, Replayer};
+
+fn bits(system: &ParticleSystem) -> Vec<u64> {
+    system
+        .particle_data()
+        .iter()
+        .chain(&system.connection_data())
+        .chain(&system.mouse_connection_data())
+        .map(|v| v.to_bits())
+        .collect()
+}
+
+/// Drives a session with every recorded call and returns each frame.
+fn session(system: &mut ParticleSystem) -> Vec<Vec<u64>> {
+    let mut frames = Vec::new();
+    for frame in 0..150 {
+        let t = frame as f64 * 0.05;
+        system.update_mouse_position(300.0 + t.cos() * 200.0, 200.0 + t.sin() * 120.0);
+        if frame % 40 == 10 {
+            system
+                .set_mouse_force(if frame % 80 == 10 { -1.5 } else { 2.0 })
+                .unwrap();
+        }
+        if frame == 60 {
+            system.resize(320.0, 240.0).unwrap();
+        }
+        if frame % 3 == 0 {
+            system.update();
+        } else {
+            system.update_with_dt(10.0 + (frame % 7) as f64 * 4.5);
+        }
+        frames.push(bits(system));
+    }
+    frames
+}
+
+#[test]
+fn replay_reproduces_every_frame() {
+    let mut system = ParticleSystem::with_seed(600.0, 400.0, 90, 90.0, 8);
+    for _ in 0..20 {
+        system.update();
+    }
+
+    system.start_recording();
+    assert!(system.is_recording());
+    let frames = session(&mut system);
+    let recording = system.stop_recording().unwrap();
+    assert!(!system.is_recording());
+    assert_eq!(recording.frame_count(), 150);
+    assert_eq!(recording.call_count(), 150 + 150 + 4 + 1);
+
+    let recording = Recording::from_bytes(&recording.to_bytes()).unwrap();
+    let mut replayer = Replayer::new(&recording).unwrap();
+    for frame in &frames {
+        assert!(replayer.next_frame());
+        assert_eq!(&bits(replayer.system()), frame);
+    }
+    assert!(replayer.is_finished());
+    assert!(!replayer.next_frame());
+    assert_eq!(replayer.into_system().snapshot(), system.snapshot());
+}
+
+#[test]
+fn recordings_export_as_json() {
+    let mut system = ParticleSystem::with_seed(400.0, 300.0, 20, 80.0, 3);
+    system.start_recording();
+    system.update_mouse_position(f64::NAN, 10.0);
+    system.resize(320.0, 240.0).unwrap();
+    system.update_with_dt(16.5);
+    let recording = system.stop_recording().unwrap();
+
+    let json = recording.to_json();
+    assert!(json.contains(r#""format": "floating-particles-recording""#));
+    assert!(json.contains(r#"["update_mouse_position", "nan", 10]"#));
+    assert!(json.contains(r#"["resize", 320, 240]"#));
+    assert!(json.contains(r#"["update_with_dt", 16.5]"#));
+
+    let parsed = Recording::from_json(&json).unwrap();
+    assert_eq!(parsed.to_bytes(), recording.to_bytes());
+    let mut replayer = Replayer::new(&parsed).unwrap();
+    assert_eq!(replayer.finish(), 1);
+    assert_eq!(bits(replayer.system()), bits(&system));
+}
+
+#[test]
+fn only_successful_calls_are_recorded() {
+    let mut system = ParticleSystem::with_seed(400.0, 300.0, 20, 80.0, 3);
+    system.update();
+    assert!(system.stop_recording().is_none());
+
+    system.start_recording();
+    assert!(system.set_mouse_force(f64::NAN).is_err());
+    assert!(system.resize(0.0, 100.0).is_err());
+    system.set_mouse_force(0.5).unwrap();
+    system.update();
+
+    let recording = system.stop_recording().unwrap();
+    assert_eq!(recording.call_count(), 2);
+    assert!(recording.to_json().contains(r#"["set_mouse_force", 0.5]"#));
+}
+
+#[test]
+fn restoring_keeps_the_recording_running() {
+    let mut system = ParticleSystem::with_seed(400.0, 300.0, 20, 80.0, 3);
+    let saved = system.snapshot();
+    system.start_recording();
+    system.update();
+
+    assert!(system.restore(&[]).is_err());
+    system.restore(&saved).unwrap();
+    assert!(system.is_recording());
+    system.update();
+    assert_eq!(system.stop_recording().unwrap().call_count(), 2);
+}
+
+#[test]
+fn invalid_recordings_are_rejected() {
+    let mut system = ParticleSystem::with_seed(400.0, 300.0, 20, 80.0, 3);
+    assert_eq!(
+        Recording::from_bytes(&system.snapshot()),
+        Err(ParticleError::Recording(
+            "not a particle system recording".to_string()
+        ))
+    );
+
+    system.start_recording();
+    system.update();
+    let json = system
+        .stop_recording()
+        .unwrap()
+        .to_json()
+        .replace(r#"["update"]"#, r#"["teleport", 1]"#);
+    let error = Recording::from_json(&json).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        r#"invalid recording: calls[0]: expected a recorded call, got ["teleport",1]"#
+    );
+}
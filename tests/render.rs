@@ -0,0 +1,94 @@
+use floating_particles::{ParticleError, ParticleSystem, Renderer};
+
+fn pixel(renderer: &Renderer, x: u32, y: u32) -> [u8; 4] {
+    let index = (y * renderer.width() + x) as usize * 4;
+    renderer.pixels()[index..index + 4].try_into().unwrap()
+}
+
+fn two_particles() -> ParticleSystem {
+    let mut system = ParticleSystem::with_seed(100.0, 100.0, 0, 60.0, 1);
+    system.set_size_range(3.0, 3.0).unwrap();
+    system
+        .add_particle_with_velocity(30.5, 50.5, 0.0, 0.0)
+        .unwrap();
+    system
+        .add_particle_with_velocity(70.5, 50.5, 0.0, 0.0)
+        .unwrap();
+    system
+}
+
+#[test]
+fn particles_are_drawn_as_discs() {
+    let system = two_particles();
+    let mut renderer = Renderer::new(100, 100).unwrap();
+    renderer.set_background(0.0, 0.0, 0.0, 1.0).unwrap();
+    renderer.render(&system);
+
+    assert_eq!(renderer.pixels().len(), 100 * 100 * 4);
+    assert_eq!(pixel(&renderer, 30, 50), [255, 255, 255, 255]);
+    assert_eq!(pixel(&renderer, 70, 50), [255, 255, 255, 255]);
+    assert_eq!(pixel(&renderer, 30, 40), [0, 0, 0, 255]);
+
+    // 边缘像素部分覆盖
+    let edge = pixel(&renderer, 33, 50);
+    assert!(edge[0] > 0 && edge[0] < 255, "{edge:?}");
+}
+
+#[test]
+fn connection_lines_use_their_opacity() {
+    let system = two_particles();
+    let mut renderer = Renderer::new(100, 100).unwrap();
+    renderer.render(&system);
+
+    let opacity = system.connection_data()[4];
+    let middle = pixel(&renderer, 50, 50);
+    assert_eq!(middle[3], (opacity * 255.0).round() as u8);
+    assert_eq!(pixel(&renderer, 50, 45), [0, 0, 0, 0]);
+}
+
+#[test]
+fn the_canvas_is_scaled_to_the_image() {
+    let system = two_particles();
+    let mut renderer = Renderer::new(200, 50).unwrap();
+    renderer.render(&system);
+
+    assert_eq!(pixel(&renderer, 61, 25)[3], 255);
+    assert_eq!(pixel(&renderer, 141, 25)[3], 255);
+}
+
+#[test]
+fn palette_colours_reach_the_image() {
+    let mut system = two_particles();
+    system.set_palette(vec![1.0, 0.0, 0.0, 1.0]).unwrap();
+    let mut renderer = Renderer::new(100, 100).unwrap();
+    renderer.render(&system);
+
+    assert_eq!(pixel(&renderer, 30, 50), [255, 0, 0, 255]);
+    assert_eq!(&pixel(&renderer, 50, 50)[..3], &[255, 0, 0]);
+}
+
+#[test]
+fn rendering_is_repeatable() {
+    let mut system = ParticleSystem::with_seed(320.0, 240.0, 120, 70.0, 9);
+    system.update_mouse_position(160.0, 120.0);
+    for _ in 0..20 {
+        system.update();
+    }
+    let mut renderer = Renderer::new(160, 120).unwrap();
+    renderer.render(&system);
+    let first = renderer.pixels().to_vec();
+    renderer.render(&system);
+    assert_eq!(renderer.pixels(), first.as_slice());
+    assert!(first.chunks(4).any(|p| p[3] > 0));
+}
+
+#[test]
+fn invalid_sizes_are_rejected() {
+    assert!(matches!(
+        Renderer::new(0, 10),
+        Err(ParticleError::InvalidValue { name: "width", .. })
+    ));
+    let mut renderer = Renderer::new(10, 10).unwrap();
+    assert!(renderer.resize(10, 100_000).is_err());
+    assert_eq!(renderer.pixels().len(), 400);
+}
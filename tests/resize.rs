@@ -0,0 +1,48 @@
+use floating_particles::{ParticleError, ParticleSystem};
+
+#[test]
+fn shrinking_clamps_particles_into_the_new_bounds() {
+    let mut system = ParticleSystem::with_seed(1000.0, 800.0, 200, 100.0, 11);
+    system.resize(300.0, 200.0).unwrap();
+
+    assert_eq!(system.width(), 300.0);
+    assert_eq!(system.height(), 200.0);
+    for particle in system.particles() {
+        assert!(particle.x <= 300.0 && particle.y <= 200.0);
+        assert!(particle.prev_x <= 300.0 && particle.prev_y <= 200.0);
+    }
+}
+
+#[test]
+fn growing_leaves_particles_in_place() {
+    let mut system = ParticleSystem::with_seed(300.0, 200.0, 50, 100.0, 11);
+    let before = system.particle_data();
+    system.resize(1000.0, 800.0).unwrap();
+    assert_eq!(system.particle_data(), before);
+}
+
+#[test]
+fn connections_follow_a_resize_without_an_update() {
+    let mut system = ParticleSystem::with_seed(2000.0, 2000.0, 300, 80.0, 12);
+    let before = system.connection_data().len();
+    system.resize(200.0, 200.0).unwrap();
+
+    // 粒子被压到一角，连接线应明显增多
+    assert!(system.connection_data().len() > before);
+}
+
+#[test]
+fn invalid_sizes_are_rejected() {
+    let mut system = ParticleSystem::with_seed(300.0, 200.0, 10, 100.0, 1);
+
+    assert!(matches!(
+        system.resize(0.0, 200.0),
+        Err(ParticleError::InvalidValue { name: "width", .. })
+    ));
+    assert!(matches!(
+        system.resize(300.0, f64::NAN),
+        Err(ParticleError::InvalidValue { name: "height", .. })
+    ));
+    assert_eq!(system.width(), 300.0);
+    assert_eq!(system.height(), 200.0);
+}
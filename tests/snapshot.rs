@@ -0,0 +1,203 @@
+use floating_particles::{
+    AttractorKind, BoundaryMode, EmitterShape, Falloff, ParticleError, ParticleSystem,
+};
+
+fn busy_system() -> ParticleSystem {
+    let mut system = ParticleSystem::with_seed(400.0, 300.0, 80, 70.0, 21);
+    system.set_boundary_mode(BoundaryMode::Respawn);
+    let attractor = system
+        .add_attractor(
+            120.0,
+            150.0,
+            80.0,
+            0.3,
+            AttractorKind::Vortex,
+            Falloff::Smooth,
+        )
+        .unwrap();
+    system.set_attractor_lifetime(attractor, 5000.0).unwrap();
+    let emitter = system
+        .add_emitter(EmitterShape::Line, 0.0, 0.0, 400.0, 0.0)
+        .unwrap();
+    system
+        .set_emitter_lifetime(emitter, 800.0, 100.0, 200.0)
+        .unwrap();
+    system
+        .add_emitter(EmitterShape::MouseTrail, 0.0, 0.0, 0.0, 0.0)
+        .unwrap();
+    for id in system.particle_ids().into_iter().step_by(7) {
+        system.remove_particle(id);
+    }
+    system
+}
+
+fn run(system: &mut ParticleSystem, from: usize, steps: usize) {
+    for step in from..from + steps {
+        let t = step as f64 * 0.07;
+        system.update_mouse_position(200.0 + t.cos() * 150.0, 150.0 + t.sin() * 100.0);
+        system.update_with_dt(20.0);
+    }
+}
+
+fn bits(system: &ParticleSystem) -> Vec<u64> {
+    system
+        .particle_data()
+        .iter()
+        .chain(&system.connection_data())
+        .chain(&system.mouse_connection_data())
+        .map(|v| v.to_bits())
+        .collect()
+}
+
+fn assert_resumes(original: &mut ParticleSystem, restored: &mut ParticleSystem) {
+    assert_eq!(bits(restored), bits(original));
+    assert_eq!(restored.particle_ids(), original.particle_ids());
+    run(original, 40, 120);
+    run(restored, 40, 120);
+    assert_eq!(bits(restored), bits(original));
+    assert_eq!(restored.particle_ids(), original.particle_ids());
+    assert_eq!(restored.snapshot(), original.snapshot());
+}
+
+#[test]
+fn binary_snapshot_resumes_exactly() {
+    let mut original = busy_system();
+    run(&mut original, 0, 40);
+
+    let blob = original.snapshot();
+    assert!(blob.starts_with(b"FPSN"));
+    let mut restored = ParticleSystem::with_seed(10.0, 10.0, 3, 5.0, 0);
+    restored.restore(&blob).unwrap();
+    assert_eq!(restored.seed(), original.seed());
+    assert_eq!(restored.emitter_count(), 2);
+    assert_resumes(&mut original, &mut restored);
+}
+
+#[test]
+fn json_snapshot_resumes_exactly() {
+    let mut original = busy_system();
+    original.set_loop_period_ms(2000.0).unwrap();
+    run(&mut original, 0, 40);
+
+    let json = original.snapshot_json();
+    assert!(json.contains(r#""format": "floating-particles-snapshot""#));
+    assert!(json.contains(r#""version": 1"#));
+    let mut restored = ParticleSystem::with_seed(10.0, 10.0, 3, 5.0, 0);
+    restored.restore_json(&json).unwrap();
+    assert_eq!(restored.snapshot_json(), json);
+    assert_resumes(&mut original, &mut restored);
+}
+
+#[test]
+fn later_additions_are_ignored() {
+    let system = busy_system();
+    let mut json = system
+        .snapshot_json()
+        .replacen(r#""version": 1"#, r#""version": 3"#, 1)
+        .replacen(r#""alpha""#, r#""future": [1, 2], "alpha""#, 1);
+    // 第一条粒子记录末尾追加一个未知字段
+    let record = json.find(r#""particles": ["#).unwrap() + r#""particles": ["#.len();
+    let close = record + json[record..].find(']').unwrap();
+    json.insert_str(close, r#", "extra""#);
+
+    let mut restored = ParticleSystem::with_seed(10.0, 10.0, 0, 5.0, 0);
+    restored.restore_json(&json).unwrap();
+    assert_eq!(bits(&restored), bits(&system));
+}
+
+#[test]
+fn incompatible_versions_are_rejected() {
+    let json = busy_system()
+        .snapshot_json()
+        .replacen(r#""version": 1"#, r#""version": 2"#, 1)
+        .replacen(r#""compatible": 1"#, r#""compatible": 2"#, 1);
+    let error = ParticleSystem::with_seed(10.0, 10.0, 0, 5.0, 0)
+        .restore_json(&json)
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid snapshot: written by format version 2, which needs a reader for version 2 \
+         or later; this build reads up to version 1"
+    );
+}
+
+#[test]
+fn corrupt_snapshots_are_rejected_without_side_effects() {
+    let blob = busy_system().snapshot();
+    let mut system = ParticleSystem::with_seed(100.0, 100.0, 5, 50.0, 9);
+    let before = system.snapshot();
+
+    let expect = |system: &mut ParticleSystem, blob: &[u8], message: &str| {
+        let error = system.restore(blob).unwrap_err();
+        assert_eq!(error, ParticleError::Snapshot(message.to_string()));
+    };
+    expect(&mut system, b"GIF89a", "not a particle system snapshot");
+    expect(
+        &mut system,
+        &blob[..blob.len() / 2],
+        "snapshot is truncated",
+    );
+    expect(&mut system, &blob[..10], "snapshot is truncated");
+
+    let mut flipped = blob.clone();
+    flipped[blob.len() / 3] ^= 0x10;
+    expect(&mut system, &flipped, "checksum mismatch");
+
+    let mut newer = blob.clone();
+    newer[4] = 9;
+    newer[6] = 9;
+    expect(
+        &mut system,
+        &newer,
+        "written by format version 9, which needs a reader for version 9 or later; \
+         this build reads up to version 1",
+    );
+
+    assert!(system.restore_json("{\"format\": 1}").is_err());
+    assert!(matches!(
+        system.restore_json("not json"),
+        Err(ParticleError::Json { .. })
+    ));
+    assert_eq!(system.snapshot(), before);
+}
+
+#[test]
+fn out_of_range_records_are_rejected() {
+    let json = busy_system().snapshot_json();
+    let mut system = ParticleSystem::with_seed(100.0, 100.0, 5, 50.0, 9);
+    let before = system.snapshot();
+
+    for (from, to, message) in [
+        (
+            "0, 3.141592653589793, 0.2, 1, 800",
+            r#"0, "inf", 0.2, 1, 800"#,
+            "emitters: spread must be between 0 and π, got inf",
+        ),
+        (
+            "0, 3.141592653589793, 0.2, 1, 800",
+            r#"0, 3.141592653589793, 0.2, "inf", 800"#,
+            "emitters: speed must be a finite range with min <= max, got [0.2, inf]",
+        ),
+        (
+            "[1, 1, 0, 0, 400, 0, 10,",
+            "[1, 1, 0, 0, 400, 0, -10,",
+            "emitters: rate must be a non-negative finite number, got -10",
+        ),
+        (
+            "[1, 120, 150, 80,",
+            r#"[1, "nan", 150, 80,"#,
+            "attractors: x must be a finite number, got NaN",
+        ),
+        (
+            "[1, 120, 150, 80,",
+            "[1, 120, 150, -80,",
+            "attractors: radius must be a non-negative finite number, got -80",
+        ),
+    ] {
+        assert!(json.contains(from));
+        let edited = json.replacen(from, to, 1);
+        let error = system.restore_json(&edited).unwrap_err();
+        assert_eq!(error, ParticleError::Snapshot(message.to_string()));
+    }
+    assert_eq!(system.snapshot(), before);
+}
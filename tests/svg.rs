@@ -0,0 +1,110 @@
+use floating_particles::{BoundaryMode, ParticleSystem, SvgGrouping, SvgOptions};
+
+fn two_particles() -> ParticleSystem {
+    let mut system = ParticleSystem::with_seed(100.0, 100.0, 0, 60.0, 1);
+    system.set_size_range(3.0, 3.0).unwrap();
+    system
+        .add_particle_with_velocity(30.5, 50.0, 0.0, 0.0)
+        .unwrap();
+    system
+        .add_particle_with_velocity(70.5, 50.0, 0.0, 0.0)
+        .unwrap();
+    system
+}
+
+fn count(svg: &str, pattern: &str) -> usize {
+    svg.matches(pattern).count()
+}
+
+#[test]
+fn particles_and_connections_become_elements() {
+    let system = two_particles();
+    let svg = system.to_svg(&SvgOptions::new());
+
+    assert!(svg.starts_with(r##"<svg xmlns="http://www.w3.org/2000/svg" width="100" height="100" viewBox="0 0 100 100">"##));
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(count(&svg, "<circle "), 2);
+    assert!(svg.contains(r##"<circle cx="30.5" cy="50" r="3" fill="#ffffff"/>"##));
+
+    let opacity = system.connection_data()[4];
+    let line = format!(
+        r##"<line x1="30.5" y1="50" x2="70.5" y2="50" stroke="#ffffff" stroke-opacity="{}"/>"##,
+        (opacity * 100.0).round() / 100.0
+    );
+    assert!(svg.contains(&line), "{svg}");
+}
+
+#[test]
+fn layers_hold_shared_attributes() {
+    let system = two_particles();
+    let mut options = SvgOptions::new();
+    options.set_stroke(1.0, 0.0, 0.0, 1.0).unwrap();
+    options.set_stroke_width(2.5).unwrap();
+
+    let svg = system.to_svg(&options);
+    assert!(svg.contains(
+        r##"<g id="connections" stroke-width="2.5" stroke-linecap="round" stroke="#ff0000">"##
+    ));
+    assert!(svg.contains(r##"<g id="particles">"##));
+    assert_eq!(count(&svg, r##"stroke="##), 2);
+
+    options.set_grouping(SvgGrouping::Flat);
+    let svg = system.to_svg(&options);
+    assert!(!svg.contains("<g"));
+    assert!(svg.contains(r##"stroke-width="2.5" stroke-linecap="round" stroke="#ff0000"/>"##));
+}
+
+#[test]
+fn view_box_and_background_are_applied() {
+    let system = two_particles();
+    let mut options = SvgOptions::new();
+    options.set_view_box(20.0, 25.0, 60.0, 50.0).unwrap();
+    options.set_background(0.0, 0.0, 0.5, 0.25).unwrap();
+
+    let svg = system.to_svg(&options);
+    assert!(svg.contains(r##"width="60" height="50" viewBox="20 25 60 50""##));
+    assert!(svg.contains(
+        r##"<rect x="20" y="25" width="60" height="50" fill="#000080" fill-opacity="0.25"/>"##
+    ));
+}
+
+#[test]
+fn mouse_connections_can_be_left_out() {
+    let mut system = two_particles();
+    system.update_mouse_position(50.0, 60.0);
+    system.update();
+
+    let mut options = SvgOptions::new();
+    let svg = system.to_svg(&options);
+    assert!(svg.contains(r##"<g id="mouse-connections""##));
+    assert!(svg.contains(r##"<line x1="50" y1="60""##));
+
+    options.set_mouse_connections(false);
+    let svg = system.to_svg(&options);
+    assert!(!svg.contains("mouse-connections"));
+    assert!(!svg.contains(r##"<line x1="50" y1="60""##));
+}
+
+#[test]
+fn wrapped_connections_are_split_at_the_edge() {
+    let mut system = ParticleSystem::with_seed(100.0, 100.0, 0, 30.0, 1);
+    system.set_boundary_mode(BoundaryMode::Wrap);
+    system
+        .add_particle_with_velocity(5.0, 50.0, 0.0, 0.0)
+        .unwrap();
+    system
+        .add_particle_with_velocity(95.0, 50.0, 0.0, 0.0)
+        .unwrap();
+
+    let svg = system.to_svg(&SvgOptions::new());
+    assert_eq!(count(&svg, "<line "), 2);
+}
+
+#[test]
+fn invalid_options_are_rejected() {
+    let mut options = SvgOptions::new();
+    assert!(options.set_view_box(0.0, 0.0, 0.0, 10.0).is_err());
+    assert!(options.set_stroke(2.0, 0.0, 0.0, 1.0).is_err());
+    assert!(options.set_stroke_width(-1.0).is_err());
+    assert_eq!(options, SvgOptions::new());
+}
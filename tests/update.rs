@@ -0,0 +1,137 @@
+use floating_particles::{BoundaryMode, ParticleConfig, ParticleSystem};
+
+fn run(system: &mut ParticleSystem, steps: usize) {
+    for step in 0..steps {
+        let t = step as f64 * 0.05;
+        system.update_mouse_position(400.0 + t.cos() * 200.0, 300.0 + t.sin() * 150.0);
+        system.update();
+    }
+}
+
+#[test]
+fn same_seed_gives_identical_state() {
+    let mut a = ParticleSystem::with_seed(800.0, 600.0, 150, 100.0, 1234);
+    let mut b = ParticleSystem::with_seed(800.0, 600.0, 150, 100.0, 1234);
+    run(&mut a, 240);
+    run(&mut b, 240);
+
+    let bits = |system: &ParticleSystem| -> Vec<u64> {
+        system.particle_data().iter().map(|v| v.to_bits()).collect()
+    };
+    assert_eq!(bits(&a), bits(&b));
+    assert_eq!(a.connection_data(), b.connection_data());
+}
+
+#[test]
+fn different_seeds_give_different_layouts() {
+    let a = ParticleSystem::with_seed(800.0, 600.0, 50, 100.0, 1);
+    let b = ParticleSystem::with_seed(800.0, 600.0, 50, 100.0, 2);
+    assert_ne!(a.particle_data(), b.particle_data());
+}
+
+#[test]
+fn string_seeds_are_stable() {
+    let a = ParticleSystem::with_string_seed(800.0, 600.0, 50, 100.0, "landing-page");
+    let b = ParticleSystem::with_string_seed(800.0, 600.0, 50, 100.0, "landing-page");
+    assert_eq!(a.seed(), b.seed());
+    assert_eq!(a.particle_data(), b.particle_data());
+
+    let c = ParticleSystem::with_seed(800.0, 600.0, 50, 100.0, a.seed());
+    assert_eq!(a.particle_data(), c.particle_data());
+}
+
+#[test]
+fn string_seeds_agree_across_entry_points() {
+    for text in [
+        "123",
+        "007",
+        "+5",
+        "landing-page",
+        "",
+        "18446744073709551615",
+        "18446744073709551616",
+    ] {
+        let system = ParticleSystem::with_string_seed(800.0, 600.0, 10, 100.0, text);
+        let mut config = ParticleConfig::default();
+        config.set_string_seed(text);
+        let json = ParticleConfig::from_json(&format!(r#"{{"seed": "{text}"}}"#)).unwrap();
+        assert_eq!(config.seed(), Some(system.seed()), "{text}");
+        assert_eq!(json.seed(), Some(system.seed()), "{text}");
+    }
+
+    let seed = |text| ParticleSystem::with_string_seed(800.0, 600.0, 10, 100.0, text).seed();
+    assert_eq!(seed("123"), 123);
+    assert_eq!(seed("007"), 7);
+    assert_eq!(seed("18446744073709551615"), u64::MAX);
+    assert_ne!(seed("+5"), 5);
+    assert_ne!(seed("18446744073709551616"), 0);
+}
+
+#[test]
+fn particles_drift_by_base_velocity_without_mouse() {
+    let mut system = ParticleSystem::with_seed(10_000.0, 10_000.0, 20, 100.0, 7);
+    let before = system.particles().to_vec();
+    system.update();
+
+    for (old, new) in before.iter().zip(system.particles()) {
+        assert_eq!(new.x, old.x + old.base_vx);
+        assert_eq!(new.y, old.y + old.base_vy);
+        assert_eq!(new.prev_x, old.x);
+        assert_eq!(new.prev_y, old.y);
+    }
+}
+
+#[test]
+fn bounce_keeps_particles_inside() {
+    let mut system = ParticleSystem::with_seed(200.0, 150.0, 100, 50.0, 3);
+    run(&mut system, 2_000);
+
+    for particle in system.particles() {
+        assert!((0.0..=200.0).contains(&particle.x));
+        assert!((0.0..=150.0).contains(&particle.y));
+    }
+}
+
+#[test]
+fn wrap_keeps_particles_inside() {
+    let mut system = ParticleSystem::with_seed(200.0, 150.0, 100, 50.0, 3);
+    system.set_boundary_mode(BoundaryMode::Wrap);
+    run(&mut system, 2_000);
+
+    for particle in system.particles() {
+        assert!((0.0..200.0).contains(&particle.x));
+        assert!((0.0..150.0).contains(&particle.y));
+    }
+}
+
+#[test]
+fn update_with_dt_is_frame_rate_independent() {
+    let mut at_60 = ParticleSystem::with_seed(800.0, 600.0, 50, 100.0, 9);
+    let mut at_120 = ParticleSystem::with_seed(800.0, 600.0, 50, 100.0, 9);
+    let step = at_60.fixed_timestep();
+
+    let steps_60: u32 = (0..60).map(|_| at_60.update_with_dt(step)).sum();
+    let steps_120: u32 = (0..120).map(|_| at_120.update_with_dt(step / 2.0)).sum();
+
+    assert_eq!(steps_60, 60);
+    assert_eq!(steps_120, 60);
+    assert_eq!(at_60.particle_data(), at_120.particle_data());
+}
+
+#[test]
+fn update_with_dt_caps_catch_up_and_reports_alpha() {
+    let mut system = ParticleSystem::with_seed(800.0, 600.0, 10, 100.0, 5);
+    let step = system.fixed_timestep();
+
+    assert_eq!(system.update_with_dt(10_000.0), system.max_catch_up_steps());
+    assert!(system.interpolation_alpha() < 1.0);
+
+    assert_eq!(system.update_with_dt(step * 0.25), 0);
+    let alpha = system.interpolation_alpha();
+    assert_eq!(system.update_with_dt(step * 0.25), 0);
+    assert!(system.interpolation_alpha() > alpha);
+
+    system.update();
+    assert_eq!(system.interpolation_alpha(), 1.0);
+    assert_eq!(system.interpolated_particle_data(), system.particle_data());
+}
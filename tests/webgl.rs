@@ -0,0 +1,85 @@
+use floating_particles::{EmitterShape, ParticleSystem};
+
+fn attribute(record: &[f32], offset: u32) -> f32 {
+    record[offset as usize / 4]
+}
+
+#[test]
+fn buffers_stay_empty_until_enabled() {
+    let mut system = ParticleSystem::with_seed(500.0, 400.0, 50, 80.0, 2);
+    system.update();
+    system.prepare_frame(false);
+    assert!(system.instance_buffer().is_empty());
+    assert!(system.line_vertex_buffer().is_empty());
+}
+
+#[test]
+fn instance_records_follow_the_layout() {
+    let mut system = ParticleSystem::with_seed(500.0, 400.0, 50, 80.0, 2);
+    system.set_palette(vec![0.25, 0.5, 0.75, 0.8]).unwrap();
+    system.set_webgl_output(true);
+    system.update();
+    system.prepare_frame(false);
+
+    let layout = system.instance_layout();
+    let stride = layout.stride as usize / 4;
+    let instances = system.instance_buffer();
+    assert_eq!(instances.len(), system.particle_count() * stride);
+
+    for (record, particle) in instances.chunks(stride).zip(system.particles()) {
+        assert_eq!(attribute(record, layout.position), particle.x as f32);
+        assert_eq!(attribute(record, layout.position + 4), particle.y as f32);
+        assert_eq!(
+            attribute(record, layout.size.unwrap()),
+            particle.size as f32
+        );
+        assert_eq!(attribute(record, layout.color), 0.25);
+        assert_eq!(attribute(record, layout.color + 8), 0.75);
+        assert_eq!(attribute(record, layout.alpha), 0.8);
+    }
+}
+
+#[test]
+fn instance_alpha_includes_fade() {
+    let mut system = ParticleSystem::with_seed(500.0, 400.0, 0, 80.0, 2);
+    let id = system
+        .add_emitter(EmitterShape::Point, 250.0, 200.0, 0.0, 0.0)
+        .unwrap();
+    system.set_emitter_rate(id, 0.0).unwrap();
+    system.set_emitter_burst(id, 1);
+    system.set_emitter_lifetime(id, 1000.0, 100.0, 0.0).unwrap();
+    system.set_webgl_output(true);
+
+    system.update();
+    system.update();
+    system.update();
+    system.prepare_frame(false);
+
+    let layout = system.instance_layout();
+    let fade = system.particles()[0].fade() as f32;
+    assert!(fade > 0.0 && fade < 1.0);
+    assert_eq!(attribute(system.instance_buffer(), layout.alpha), fade);
+}
+
+#[test]
+fn line_vertices_pair_up_with_connection_segments() {
+    let mut system = ParticleSystem::with_seed(500.0, 400.0, 150, 80.0, 3);
+    system.set_webgl_output(true);
+    system.update();
+    system.prepare_frame(false);
+
+    let layout = system.line_vertex_layout();
+    assert_eq!(layout.size, None);
+    let stride = layout.stride as usize / 4;
+    let vertices = system.line_vertex_buffer();
+    let segments = system.connection_buffer();
+    assert_eq!(vertices.len() / (stride * 2), segments.len() / 5);
+
+    for (pair, segment) in vertices.chunks(stride * 2).zip(segments.chunks(5)) {
+        let (start, end) = pair.split_at(stride);
+        assert_eq!(attribute(start, layout.position), segment[0]);
+        assert_eq!(attribute(end, layout.position + 4), segment[3]);
+        assert_eq!(attribute(start, layout.alpha), segment[4]);
+        assert_eq!(attribute(end, layout.alpha), segment[4]);
+    }
+}